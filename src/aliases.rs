@@ -0,0 +1,78 @@
+use crate::{index::Index, markdown, note, note::Note, utils};
+
+/// Adds an alias to a note, which can then be used in place of its title.
+///
+/// * `path` - raw input from the user such as `foo/bar`
+/// * `alias` - the alternative title, such as `baz`
+///
+/// Aliases are stored in the index and in the `aliases` frontmatter key.
+pub fn add_alias(path: &str, alias: &str) -> anyhow::Result<()> {
+    let alias = note::validate_alias(alias)?;
+
+    update_aliases(path, |aliases| {
+        if !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+
+        Ok(())
+    })
+}
+
+/// Removes an alias from a note.
+///
+/// * `path` - raw input from the user such as `foo/bar`
+/// * `alias` - the alias to remove
+pub fn remove_alias(path: &str, alias: &str) -> anyhow::Result<()> {
+    let alias = alias.trim();
+
+    update_aliases(path, |aliases| {
+        if !aliases.iter().any(|a| a == alias) {
+            return Err(anyhow::anyhow!("Error: Note has no alias \"{alias}\""));
+        }
+
+        aliases.retain(|a| a != alias);
+
+        Ok(())
+    })
+}
+
+/// Applies a change to the aliases of a note and writes them back to its
+/// frontmatter and the index.
+fn update_aliases(
+    path: &str,
+    update: impl FnOnce(&mut Vec<String>) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut note = utils::get_note(path, false)?;
+
+    // pick up any aliases added to the frontmatter since the last edit
+    utils::sync_note(&mut note)?;
+    update(&mut note.aliases)?;
+
+    write_aliases(&note)?;
+
+    let index = Index::open()?;
+    index.insert(&note)?;
+
+    Ok(())
+}
+
+fn write_aliases(note: &Note) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(&note.absolute_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read note file: {}: {e}", note.absolute_path))?;
+
+    let (frontmatter, _) = markdown::split_frontmatter(&content);
+
+    // the singular key would otherwise be read back if we remove the last alias
+    let content = match frontmatter {
+        Some(f) if f.lines().any(|l| l.starts_with("alias:")) => {
+            markdown::set_frontmatter_values(&content, "alias", &[])
+        }
+        _ => content,
+    };
+    let content = markdown::set_frontmatter_values(&content, "aliases", &note.aliases);
+
+    std::fs::write(&note.absolute_path, content)
+        .map_err(|e| anyhow::anyhow!("Failed to write note file: {}: {e}", note.absolute_path))?;
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+
+use colored::Colorize;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{cli::Conflict, file, index::Index, note::Note, path::NotePath};
+
+const MANIFEST: &str = "manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+
+/// The list of notes stored in a backup, alongside their files.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    notes: Vec<Note>,
+}
+
+/// The outcome of a backup.
+#[derive(Debug, Default)]
+pub struct BackupSummary {
+    pub backed_up: usize,
+    pub skipped: usize,
+}
+
+/// The outcome of a restore.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub restored: usize,
+    pub skipped: usize,
+}
+
+/// Bundles every note into a `.tar.gz` archive.
+///
+/// * `archive` - path of the archive to create
+///
+/// The archive holds a `manifest.json` with the [Note] records (tags and
+/// timestamps) and each note file under `notes/<relative path>.md`.
+/// Indexed notes whose file is missing are reported and left out.
+pub fn backup(archive: &Path) -> anyhow::Result<BackupSummary> {
+    let mut summary = BackupSummary::default();
+    let mut notes = Vec::new();
+
+    for note in Index::open()?.get_all()? {
+        if Path::new(&note.absolute_path).is_file() {
+            notes.push(note);
+        } else {
+            eprintln!(
+                "{}",
+                format!("Skipping {}: note file is missing", note.relative_path).bright_yellow()
+            );
+            summary.skipped += 1;
+        }
+    }
+
+    notes.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    let file = File::create(archive)
+        .map_err(|e| anyhow::anyhow!("Failed to create backup file: {}: {e}", archive.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        notes,
+    };
+    let json = serde_json::to_vec_pretty(&manifest)?;
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp() as u64);
+    builder.append_data(&mut header, MANIFEST, json.as_slice())?;
+
+    for note in &manifest.notes {
+        let name = format!("notes/{}.md", note.relative_path);
+
+        builder
+            .append_path_with_name(&note.absolute_path, &name)
+            .map_err(|e| anyhow::anyhow!("Failed to add note file: {}: {e}", note.absolute_path))?;
+    }
+
+    builder.into_inner()?.finish()?;
+    summary.backed_up = manifest.notes.len();
+
+    Ok(summary)
+}
+
+/// Unpacks a backup made by [backup] into the root and rebuilds the index.
+///
+/// * `archive` - path of the archive to restore
+/// * `conflict` - what to do with notes that already exist
+///
+/// Notes that aren't in the backup are kept. Index records whose note file
+/// no longer exists don't block a restored note, which replaces them.
+/// Records that are missing from the archive or have an invalid path are
+/// reported and skipped.
+pub fn restore(archive: &Path, conflict: Conflict) -> anyhow::Result<Summary> {
+    let file = File::open(archive)
+        .map_err(|e| anyhow::anyhow!("Failed to open backup file: {}: {e}", archive.display()))?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+
+    let mut manifest: Option<Manifest> = None;
+    let mut files = HashMap::new();
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        let mut contents = Vec::new();
+
+        entry.read_to_end(&mut contents)?;
+
+        if name == MANIFEST {
+            manifest = Some(
+                serde_json::from_slice(&contents)
+                    .map_err(|e| anyhow::anyhow!("Failed to read backup manifest: {e}"))?,
+            );
+        } else if let Some(path) = name
+            .strip_prefix("notes/")
+            .and_then(|n| n.strip_suffix(".md"))
+        {
+            files.insert(path.to_string(), contents);
+        }
+    }
+
+    let manifest = manifest
+        .ok_or_else(|| anyhow::anyhow!("Error: {} is not a jottem backup", archive.display()))?;
+
+    if manifest.version > MANIFEST_VERSION {
+        return Err(anyhow::anyhow!(
+            "Error: Backup was made by a newer version of jottem (manifest version {})",
+            manifest.version
+        ));
+    }
+
+    let index = Index::open()?;
+    let mut summary = Summary::default();
+
+    for record in manifest.notes {
+        let Some(contents) = files.get(&record.relative_path) else {
+            eprintln!(
+                "{}",
+                format!("Skipping {}: missing from the backup", record.relative_path)
+                    .bright_yellow()
+            );
+            summary.skipped += 1;
+            continue;
+        };
+
+        let mut path = match NotePath::parse_contained(&record.relative_path) {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Skipping {}: {e}", record.relative_path).bright_yellow()
+                );
+                summary.skipped += 1;
+                continue;
+            }
+        };
+        let exists = |path: &NotePath| -> anyhow::Result<bool> {
+            // a record without a file is replaced by the restored note
+            Ok(file::exists(path)
+                || index
+                    .find_by_path(path)?
+                    .iter()
+                    .any(|note| Path::new(&note.absolute_path).is_file()))
+        };
+
+        if exists(&path)? {
+            match conflict {
+                Conflict::Skip => {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "Skipping {}: a note already exists there",
+                            path.relative_path()
+                        )
+                        .bright_yellow()
+                    );
+                    summary.skipped += 1;
+                    continue;
+                }
+                Conflict::Overwrite => {}
+                Conflict::Rename => {
+                    let title = path.title.clone();
+                    let mut n = 1;
+
+                    while exists(&path)? {
+                        path.title = format!("{title}-{n}");
+                        n += 1;
+                    }
+                }
+            }
+        }
+
+        let absolute_path = path.absolute_path_with_ext();
+
+        if let Some(parent) = Path::new(&absolute_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&absolute_path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write note file: {absolute_path}: {e}"))?;
+
+        let note = Note {
+            absolute_path,
+            relative_path: path.relative_path(),
+            title: path.title.clone(),
+            ..record
+        };
+
+        index.insert(&note)?;
+
+        println!("{}", note.relative_path);
+        summary.restored += 1;
+    }
+
+    Ok(summary)
+}
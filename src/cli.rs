@@ -1,9 +1,34 @@
-use clap::{command, Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Parser)]
+#[command(version, about)]
 pub struct Cli {
     #[clap(subcommand)]
     pub command: Command,
+
+    #[arg(
+        long,
+        global = true,
+        help = "never prompt for input; exit with status 3 if no notes match, 4 if several notes match \
+                or 5 if input is required (implied when stdin is not a terminal)"
+    )]
+    pub no_input: bool,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        help = "answer yes when asked to confirm changes to several notes"
+    )]
+    pub yes: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "only accept notes whose title, alias or path matches exactly, instead of offering similar notes"
+    )]
+    pub exact: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -22,27 +47,72 @@ pub enum Command {
             help = "a list of tags to add to the note"
         )]
         tags: Vec<String>,
+
+        #[arg(
+            long,
+            required = false,
+            help = "write stdin into the note instead of opening the editor"
+        )]
+        stdin: bool,
+    },
+
+    /// Append stdin to a note
+    #[clap(name = "append", alias = "a", about = "[a]ppend stdin to a note")]
+    Append {
+        #[arg(help = "note title or relative path")]
+        path: String,
+    },
+
+    /// Jot a quick thought into the inbox note
+    #[clap(name = "jot", alias = "j", about = "[j]ot a thought into the inbox")]
+    Jot {
+        #[arg(required = true, help = "text to jot down")]
+        text: Vec<String>,
+    },
+
+    /// Open or process the inbox note
+    #[clap(name = "inbox", alias = "i", about = "open the [i]nbox note")]
+    Inbox {
+        #[clap(subcommand)]
+        subcommand: Option<InboxCommand>,
     },
 
     /// Edit an existing note
     #[clap(name = "edit", alias = "e", about = "[e]dit a note")]
     Edit {
-        #[arg(help = "note title or relative path")]
+        #[arg(help = "note title or relative path (omit to choose from a list)")]
         path: Option<String>,
     },
 
+    /// Print a note to stdout
+    #[clap(name = "show", alias = "cat", about = "print a note to stdout")]
+    Show {
+        #[command(flatten)]
+        args: ShowArgs,
+    },
+
     /// Search for notes
     #[clap(name = "find", alias = "f", about = "[f]ind a note")]
     Find {
         #[command(flatten)]
         args: SearchArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Save and run searches
+    #[clap(name = "search", alias = "s", about = "manage [s]aved searches")]
+    Search {
+        #[clap(subcommand)]
+        subcommand: SearchCommand,
     },
 
     /// Delete a note
     #[clap(name = "delete", alias = "d", about = "[d]elete a note")]
     Delete {
-        #[arg(help = "note title or relative path")]
-        path: String,
+        #[arg(help = "note title or relative path (omit to choose from a list)")]
+        path: Option<String>,
     },
 
     /// Manage tags on a note
@@ -52,6 +122,13 @@ pub enum Command {
         subcommand: TagCommand,
     },
 
+    /// Manage aliases on a note
+    #[clap(name = "alias", about = "manage alternative titles for a note")]
+    Alias {
+        #[clap(subcommand)]
+        subcommand: AliasCommand,
+    },
+
     // /// Rename a note
     // #[clap(name = "rename", alias = "r", about = "[r]ename a note")]
     // Rename {
@@ -62,13 +139,23 @@ pub enum Command {
     //     new_title: String,
     // },
     /// Move a note
+    ///
+    /// To choose the note to move from a list, omit the path and give the
+    /// new path with `--to`.
     #[clap(name = "move", alias = "m", about = "[m]ove a note")]
     Move {
         #[arg(help = "note title or relative path")]
-        path: String,
+        path: Option<String>,
+
+        #[arg(help = "new relative path", required_unless_present = "to")]
+        new_path: Option<String>,
 
-        #[arg(help = "new relative path")]
-        new_path: String,
+        #[arg(
+            long,
+            conflicts_with = "new_path",
+            help = "new relative path for a note chosen from a list"
+        )]
+        to: Option<String>,
 
         #[arg(
             help = "rename the note in place without moving",
@@ -79,6 +166,162 @@ pub enum Command {
         rename: bool,
     },
 
+    /// Move or rename a folder of notes
+    #[clap(
+        name = "move-folder",
+        alias = "mf",
+        about = "[m]ove a [f]older of notes"
+    )]
+    MoveFolder {
+        #[arg(help = "relative folder path")]
+        folder: String,
+
+        #[arg(help = "new relative folder path")]
+        new_folder: String,
+
+        #[arg(
+            help = "rename the folder in place without moving",
+            short,
+            long,
+            required = false
+        )]
+        rename: bool,
+    },
+
+    /// Delete a folder of notes
+    #[clap(
+        name = "delete-folder",
+        alias = "df",
+        about = "[d]elete a [f]older of notes"
+    )]
+    DeleteFolder {
+        #[arg(help = "relative folder path")]
+        folder: String,
+    },
+
+    /// Browse notes in a full-screen terminal UI
+    #[clap(name = "tui", alias = "u", about = "browse notes in a terminal [u]i")]
+    Tui,
+
+    /// Serve notes over a local HTTP/JSON API
+    #[clap(name = "serve", about = "serve notes over a local HTTP/JSON API")]
+    Serve {
+        #[arg(short, long, default_value_t = 4000, help = "port to listen on")]
+        port: u16,
+
+        #[arg(
+            short,
+            long,
+            default_value = "127.0.0.1",
+            help = "address to listen on"
+        )]
+        bind: String,
+
+        #[arg(
+            long,
+            help = "require `Authorization: Bearer <TOKEN>` on every request (defaults to $JOTTEM_API_TOKEN)"
+        )]
+        token: Option<String>,
+
+        #[arg(
+            long = "allow-origin",
+            value_name = "ORIGIN",
+            help = "let web pages from this origin, such as `http://localhost:5173`, call the API (defaults to $JOTTEM_API_ORIGINS)"
+        )]
+        origins: Vec<String>,
+    },
+
+    /// Keep the index in sync with changes made by other tools
+    #[clap(
+        name = "watch",
+        about = "keep the index in sync with note files changed by other tools"
+    )]
+    Watch,
+
+    /// Display notes as a directory tree
+    #[clap(name = "tree", about = "display notes as a directory tree")]
+    Tree {
+        #[command(flatten)]
+        args: TreeArgs,
+    },
+
+    /// Generate shell completions
+    #[clap(name = "completions", about = "generate shell completions")]
+    Completions {
+        #[arg(value_enum, help = "shell to generate completions for")]
+        shell: CompletionShell,
+    },
+
+    /// Generate man pages
+    #[clap(name = "man", about = "generate man pages")]
+    Man {
+        #[arg(help = "directory to write the man pages into")]
+        out_dir: String,
+    },
+
+    /// Print dynamic completion candidates (used by the completion scripts)
+    #[clap(name = "complete", hide = true)]
+    Complete {
+        #[arg(long, help = "position of the word being completed")]
+        index: usize,
+
+        #[arg(last = true, help = "the words on the command line")]
+        words: Vec<String>,
+    },
+
+    /// Import a directory of markdown files
+    #[clap(
+        name = "import",
+        about = "import a directory of markdown files (such as an Obsidian vault)"
+    )]
+    Import {
+        #[arg(help = "directory to import")]
+        dir: String,
+
+        #[arg(
+            short,
+            long,
+            conflicts_with = "in_place",
+            help = "folder to import the notes into"
+        )]
+        folder: Option<String>,
+
+        #[arg(
+            long,
+            help = "adopt files already inside the notes directory instead of copying them"
+        )]
+        in_place: bool,
+    },
+
+    /// Back up every note into an archive
+    #[clap(name = "backup", about = "back up every note into a .tar.gz archive")]
+    Backup {
+        #[arg(help = "archive to create, such as notes.tar.gz")]
+        file: String,
+    },
+
+    /// Restore notes from a backup archive
+    #[clap(name = "restore", about = "restore notes from a backup archive")]
+    Restore {
+        #[arg(help = "archive created by `jottem backup`")]
+        file: String,
+
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = Conflict::Skip,
+            help = "what to do with notes that already exist"
+        )]
+        conflict: Conflict,
+    },
+
+    /// Render notes to a static HTML site
+    #[clap(name = "export-site", about = "render notes to a static HTML site")]
+    ExportSite {
+        #[arg(help = "directory to write the site into")]
+        out_dir: String,
+    },
+
     /// Export notes index as JSON
     #[clap(
         name = "export",
@@ -88,51 +331,343 @@ pub enum Command {
     Export,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum AliasCommand {
+    #[command(about = "add an alias to a note")]
+    Add {
+        #[arg(help = "note title or relative path")]
+        path: String,
+
+        #[arg(help = "alternative title for the note")]
+        alias: String,
+    },
+
+    #[command(about = "remove an alias from a note")]
+    Remove {
+        #[arg(help = "note title or relative path")]
+        path: String,
+
+        #[arg(help = "alias to remove")]
+        alias: String,
+    },
+}
+
+/// Saved searches take the same criteria as `jottem find`.
+#[derive(Debug, Subcommand)]
+pub enum SearchCommand {
+    #[command(about = "save the criteria of a search under a name")]
+    Save {
+        #[arg(help = "name of the search, such as `standup`")]
+        name: String,
+
+        #[command(flatten)]
+        args: Box<SearchArgs>,
+    },
+
+    #[command(about = "list every saved search")]
+    List,
+
+    #[command(about = "find the notes matching a saved search")]
+    Run {
+        #[arg(help = "name of the search")]
+        name: String,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    #[command(about = "delete a saved search")]
+    Delete {
+        #[arg(help = "name of the search")]
+        name: String,
+    },
+}
+
+/// To choose the notes for `add` or `remove` from a list, omit the path
+/// and give the tags with `--tags`.
 #[derive(Debug, Subcommand)]
 pub enum TagCommand {
     #[command(about = "add tags to an existing note")]
     Add {
         #[arg(help = "note title or relative path")]
-        path: String,
+        path: Option<String>,
 
-        #[arg(help = "list of tags to add", value_delimiter = ',')]
+        #[arg(
+            help = "list of tags to add",
+            value_delimiter = ',',
+            required_unless_present = "tag"
+        )]
         tags: Vec<String>,
+
+        #[arg(
+            short,
+            long = "tags",
+            value_delimiter = ',',
+            help = "tags to add, to notes chosen from a list if no path is given"
+        )]
+        tag: Vec<String>,
     },
 
     #[command(about = "remove tags from an existing note")]
     Remove {
         #[arg(help = "note title or relative path")]
-        path: String,
+        path: Option<String>,
 
-        #[arg(help = "list of tags to remove", value_delimiter = ',')]
+        #[arg(
+            help = "list of tags to remove",
+            value_delimiter = ',',
+            required_unless_present = "tag"
+        )]
         tags: Vec<String>,
+
+        #[arg(
+            short,
+            long = "tags",
+            value_delimiter = ',',
+            help = "tags to remove, from notes chosen from a list if no path is given"
+        )]
+        tag: Vec<String>,
+    },
+
+    #[command(about = "list every tag as a tree")]
+    List,
+
+    #[command(about = "normalize the tags of every existing note")]
+    Normalize {
+        #[arg(long, help = "only print the changes that would be made")]
+        dry_run: bool,
+    },
+
+    #[command(about = "rename a tag along with all of its descendants")]
+    Rename {
+        #[arg(help = "tag to rename, such as `project`")]
+        tag: String,
+
+        #[arg(help = "new name for the tag, such as `work/project`")]
+        new_tag: String,
     },
 }
 
+/// Shells supported by `jottem completions`.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// How `jottem find` orders the matching notes.
+#[derive(Debug, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// most relevant to `--text` first
+    Score,
+    /// alphabetically by relative path
+    Path,
+    /// most recently modified first
+    Modified,
+    /// most recently created first
+    Created,
+}
+
+/// How `jottem find` prints the matching notes.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// a table of paths and modified times
+    Table,
+    /// the note records as a JSON array
+    Json,
+    /// one relative path per line
+    Paths,
+}
+
+/// What `jottem restore` does with notes that already exist.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Conflict {
+    /// keep the existing note
+    Skip,
+    /// replace the existing note
+    Overwrite,
+    /// restore the note next to the existing one with a numbered title
+    Rename,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum InboxCommand {
+    #[command(about = "move each inbox entry into a new or existing note")]
+    Process,
+}
+
 /// Search parameters used for finding notes.
 ///
 /// * `path` (`-p`, `--path`) - a typical path such as `foo/bar`
 /// * `tags` (`-t`, `--tags`) - a comma-separated list of tags
 /// * `all` (`-a`, `--all`) - takes no arguments, returns all notes
+/// * `glob` (`-g`, `--glob`) - a glob pattern such as `projects/*/todo`
+/// * `regex` (`-r`, `--regex`) - a regular expression such as `^daily/2024-`
+/// * `folder` (`--in`) - a folder such as `projects/`
+/// * `created_after`, `created_before`, `modified_after`, `modified_before`
+///   - a date such as `2024-01-15` or a time ago such as `7d`
+/// * `text` (`-x`, `--text`) - words that must all appear in the note
+/// * `sort` (`--sort`) - the order of the results. See [SortOrder]
 ///
-/// All three fields (flags) are mutually exclusive.
+/// Any combination of criteria may be given, and only notes matching all
+/// of them are returned. `all` is the default when no criteria are given,
+/// so it can't be combined with any.
 ///
 /// `path` requires a path or note title (such as `foo/bar` or `baz`)
 /// to be provided.
 ///
 /// `tags` requires a comma-separated list of one or more tags to be
-/// provided.
+/// provided. Notes with any of the tags match.
 ///
-/// `all` requires no arguments and returns all notes
-#[derive(Debug, Clone, Args)]
-#[group(required = true, multiple = false)]
+/// `glob` and `regex` are matched against the relative path of each note,
+/// and `folder` matches every note under the folder, including subfolders.
+///
+/// Dates are compared to the created or modified time of each note, with
+/// the `after` bound included and the `before` bound excluded.
+///
+/// Saved searches store these as JSON, so fields added later need defaults.
+#[derive(Debug, Clone, Default, Args, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SearchArgs {
-    #[arg(short, long)]
+    #[arg(short, long, help = "note title or relative path")]
     pub path: Option<String>,
 
-    #[arg(short, long, value_delimiter = ',')]
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        help = "match notes with any of these tags"
+    )]
     pub tags: Vec<String>,
 
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = [
+            "path", "tags", "glob", "regex", "folder", "created_after",
+            "created_before", "modified_after", "modified_before", "text",
+        ],
+        help = "list every note (the default without other criteria)"
+    )]
     pub all: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "match relative paths against a glob pattern such as `projects/*/todo`"
+    )]
+    pub glob: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "match relative paths against a regular expression"
+    )]
+    pub regex: Option<String>,
+
+    #[arg(
+        long = "in",
+        help = "match every note under a folder such as `projects/`"
+    )]
+    pub folder: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "match notes created on or after a date such as `2024-01-15` or `7d` (ago)"
+    )]
+    pub created_after: Option<String>,
+
+    #[arg(long, value_name = "DATE", help = "match notes created before a date")]
+    pub created_before: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "match notes modified on or after a date such as `2024-01-15` or `7d` (ago)"
+    )]
+    pub modified_after: Option<String>,
+
+    #[arg(long, value_name = "DATE", help = "match notes modified before a date")]
+    pub modified_before: Option<String>,
+
+    #[arg(
+        short = 'x',
+        long,
+        help = "match notes containing all of these words, ignoring case"
+    )]
+    pub text: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "order of the notes (defaults to score with --text, otherwise path)"
+    )]
+    pub sort: Option<SortOrder>,
+}
+
+/// Output options for printing the notes found by a search.
+///
+/// * `format` (`--format`) - a table, JSON or a list of paths
+/// * `score` (`--score`) - add a column with the relevance of each note
+///   to the search text to the table
+#[derive(Debug, Clone, Args)]
+pub struct OutputArgs {
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Table,
+        help = "how to print the matching notes"
+    )]
+    pub format: OutputFormat,
+
+    #[arg(long, help = "show how relevant each note is to --text")]
+    pub score: bool,
+}
+
+/// Display options for the notes tree.
+///
+/// * `folder` - optional relative folder path such as `foo/bar`
+/// * `depth` (`-d`, `--depth`) - maximum number of folder levels to expand
+/// * `tags` (`-t`, `--tags`) - show the tags on each note
+/// * `modified` (`-m`, `--modified`) - show the last modified time of each note
+#[derive(Debug, Clone, Args)]
+pub struct TreeArgs {
+    #[arg(help = "folder to display (defaults to the root)")]
+    pub folder: Option<String>,
+
+    #[arg(short, long, help = "maximum number of folder levels to expand")]
+    pub depth: Option<usize>,
+
+    #[arg(short, long, help = "show the tags on each note")]
+    pub tags: bool,
+
+    #[arg(short, long, help = "show the last modified time of each note")]
+    pub modified: bool,
+}
+
+/// Output options for printing a note.
+///
+/// * `path` - a typical path or note title such as `foo/bar`, or `None`
+///   to choose the note from a list
+/// * `no_frontmatter` (`-f`, `--no-frontmatter`) - omit the YAML frontmatter
+/// * `color` (`-c`, `--color`) - highlight headings with terminal colors
+/// * `section` (`-s`, `--section`) - only print the section under a heading
+///
+/// Printing a section implies `no_frontmatter`.
+#[derive(Debug, Clone, Args)]
+pub struct ShowArgs {
+    #[arg(help = "note title or relative path (omit to choose from a list)")]
+    pub path: Option<String>,
+
+    #[arg(short = 'f', long, help = "omit the frontmatter")]
+    pub no_frontmatter: bool,
+
+    #[arg(short, long, help = "highlight headings with terminal colors")]
+    pub color: bool,
+
+    #[arg(short, long, help = "only print the section under this heading")]
+    pub section: Option<String>,
 }
@@ -0,0 +1,302 @@
+use std::{collections::BTreeSet, path::Path};
+
+use clap::{Arg, CommandFactory};
+
+use crate::{
+    cli::{Cli, CompletionShell},
+    config,
+    index::Index,
+};
+
+/// The kinds of values that are completed dynamically from the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completion {
+    Notes,
+    Tags,
+    Folders,
+}
+
+/// Prints the completion script for a shell to stdout.
+///
+/// The static part of the script is generated by `clap_complete` from the
+/// [Cli] definitions. A shell-specific wrapper is added on top which asks
+/// `jottem complete` for note paths and tags, falling back to the static
+/// completions for everything else.
+pub fn print_script(shell: CompletionShell) -> anyhow::Result<()> {
+    let mut cmd = Cli::command();
+    let mut script = Vec::new();
+
+    let generator = match shell {
+        CompletionShell::Bash => clap_complete::Shell::Bash,
+        CompletionShell::Zsh => clap_complete::Shell::Zsh,
+        CompletionShell::Fish => clap_complete::Shell::Fish,
+    };
+
+    clap_complete::generate(generator, &mut cmd, "jottem", &mut script);
+    let script = String::from_utf8(script)?;
+
+    let script = match shell {
+        CompletionShell::Bash => format!("{script}\n{BASH_DYNAMIC}"),
+        CompletionShell::Zsh => zsh_script(&script),
+        CompletionShell::Fish => format!("{script}\n{FISH_DYNAMIC}"),
+    };
+
+    print!("{script}");
+
+    Ok(())
+}
+
+/// Prints the dynamic completion candidates for the word under the cursor.
+///
+/// * `index` - position of the word being completed within `words`
+/// * `words` - the full command line, starting with `jottem`
+///
+/// Nothing is printed if the word isn't a note path, tag or folder, which
+/// tells the shell to use the static completions instead. Nothing is printed
+/// either if there is no index yet, or it can't be opened.
+pub fn print_candidates(index: usize, words: &[String]) -> anyhow::Result<()> {
+    let Some((completion, prefix)) = completion_for(words, index) else {
+        return Ok(());
+    };
+
+    let current = words.get(index).map(String::as_str).unwrap_or_default();
+    let current = current.strip_prefix(prefix.as_str()).unwrap_or(current);
+
+    // opening the index would create it
+    if !Path::new(&config::get_db_path()).exists() {
+        return Ok(());
+    }
+
+    let Ok(index) = Index::open() else {
+        return Ok(());
+    };
+
+    let candidates: BTreeSet<String> = match completion {
+        Completion::Notes => index
+            .get_all()?
+            .into_iter()
+            .map(|n| n.relative_path)
+            .collect(),
+        Completion::Tags => {
+            // tags are comma-separated, so we only complete the last one
+            let typed = match current.rsplit_once(',') {
+                Some((typed, _)) => format!("{typed},"),
+                None => String::new(),
+            };
+            let used = typed.split(',').collect::<Vec<&str>>();
+
+            index
+                .get_all()?
+                .into_iter()
+                .flat_map(|n| n.tags)
+                .filter(|t| !used.contains(&t.as_str()))
+                .map(|t| format!("{typed}{t}"))
+                .collect()
+        }
+        Completion::Folders => index
+            .get_all()?
+            .into_iter()
+            .flat_map(|n| {
+                let path = n.relative_path;
+
+                path.match_indices('/')
+                    .map(|(i, _)| path[..i].to_string())
+                    .collect::<Vec<String>>()
+            })
+            .collect(),
+    };
+
+    for candidate in candidates {
+        println!("{prefix}{candidate}");
+    }
+
+    Ok(())
+}
+
+/// Works out what kind of value is being typed at `words[index]`.
+///
+/// Returns the kind of completion along with any prefix that has to be kept
+/// in front of each candidate (such as `--tags=`).
+pub fn completion_for(words: &[String], index: usize) -> Option<(Completion, String)> {
+    let mut cmd = Cli::command();
+    cmd.build();
+
+    let mut current = &cmd;
+    let mut positional = 0;
+    let mut expecting: Option<&Arg> = None;
+
+    for word in words.iter().take(index).skip(1) {
+        if expecting.take().is_some() {
+            continue;
+        }
+
+        if let Some(long) = word.strip_prefix("--") {
+            if !long.contains('=') {
+                expecting = current
+                    .get_arguments()
+                    .find(|a| a.get_long() == Some(long))
+                    .filter(|a| a.get_action().takes_values());
+            }
+        } else if let Some(shorts) = word.strip_prefix('-').filter(|s| !s.is_empty()) {
+            // only the last flag in a cluster like `-ab` can take a separate value
+            if shorts.chars().count() == 1 {
+                let short = shorts.chars().next();
+                expecting = current
+                    .get_arguments()
+                    .find(|a| a.get_short() == short)
+                    .filter(|a| a.get_action().takes_values());
+            }
+        } else if let Some(subcommand) = current.find_subcommand(word).filter(|_| positional == 0) {
+            current = subcommand;
+        } else {
+            positional += 1;
+        }
+    }
+
+    let word = words.get(index).map(String::as_str).unwrap_or_default();
+
+    let (arg, prefix) = if let Some(arg) = expecting {
+        (arg, String::new())
+    } else if let Some((option, _)) = word.strip_prefix("--").and_then(|w| w.split_once('=')) {
+        let arg = current
+            .get_arguments()
+            .find(|a| a.get_long() == Some(option))?;
+
+        (arg, format!("--{option}="))
+    } else if word.starts_with('-') {
+        return None;
+    } else {
+        let mut positionals = current.get_positionals().collect::<Vec<&Arg>>();
+        let last = positionals.pop()?;
+
+        // a trailing positional with multiple values takes every remaining word
+        let arg = positionals.get(positional).copied().or_else(|| {
+            let many = last.get_num_args().is_some_and(|n| n.max_values() > 1);
+
+            (positional == positionals.len() || many).then_some(last)
+        })?;
+
+        (arg, String::new())
+    };
+
+    let completion = match arg.get_id().as_str() {
+        "path" => Completion::Notes,
+        "tags" | "tag" => Completion::Tags,
+        "folder" => Completion::Folders,
+        _ => return None,
+    };
+
+    Some((completion, prefix))
+}
+
+/// Hooks the dynamic completions into the generated zsh script.
+///
+/// The generated script ends by registering `_jottem`, either directly or by
+/// calling it when autoloaded from `fpath`. We register our wrapper instead,
+/// in both cases, so later completions keep using it.
+fn zsh_script(script: &str) -> String {
+    let tail = "if [ \"$funcstack[1]\" = \"_jottem\" ]; then";
+
+    match script.rfind(tail) {
+        Some(pos) => {
+            let (head, tail) = script.split_at(pos);
+            let tail = tail
+                .replace(
+                    "_jottem \"$@\"",
+                    "compdef _jottem_dynamic jottem\n    _jottem_dynamic \"$@\"",
+                )
+                .replace("compdef _jottem jottem", "compdef _jottem_dynamic jottem");
+
+            format!("{head}{ZSH_DYNAMIC}\n{tail}")
+        }
+        None => format!("{script}\n{ZSH_DYNAMIC}\ncompdef _jottem_dynamic jottem\n"),
+    }
+}
+
+const BASH_DYNAMIC: &str = r#"_jottem_dynamic() {
+    local candidates
+    candidates="$(jottem complete --index "$COMP_CWORD" -- "${COMP_WORDS[@]}" 2>/dev/null)"
+
+    if [[ -n "$candidates" ]]; then
+        local IFS=$'\n'
+        COMPREPLY=( $(compgen -W "$candidates" -- "${COMP_WORDS[COMP_CWORD]}") )
+    else
+        _jottem "$@"
+    fi
+}
+
+complete -F _jottem_dynamic -o bashdefault -o default jottem
+"#;
+
+const ZSH_DYNAMIC: &str = r#"_jottem_dynamic() {
+    local -a candidates
+    candidates=("${(@f)$(jottem complete --index $((CURRENT - 1)) -- "${words[@]}" 2>/dev/null)}")
+
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -a candidates
+    else
+        _jottem "$@"
+    fi
+}
+"#;
+
+const FISH_DYNAMIC: &str = r#"complete -c jottem -a '(jottem complete --index (count (commandline -opc)) -- (commandline -opc) (commandline -ct) 2>/dev/null)'
+"#;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn complete(line: &str) -> Option<(Completion, String)> {
+        let mut words = line.split(' ').map(String::from).collect::<Vec<String>>();
+        let index = words.len() - 1;
+
+        if words[index] == "_" {
+            words[index] = String::new();
+        }
+
+        completion_for(&words, index)
+    }
+
+    #[test]
+    fn complete_note_paths() {
+        let notes = Some((Completion::Notes, String::new()));
+
+        assert_eq!(complete("jottem edit _"), notes);
+        assert_eq!(complete("jottem e fo"), notes);
+        assert_eq!(complete("jottem move foo"), notes);
+        assert_eq!(complete("jottem tag add fo"), notes);
+        assert_eq!(complete("jottem find --path fo"), notes);
+        assert_eq!(complete("jottem move foo _"), None);
+        assert_eq!(complete("jottem _"), None);
+        assert_eq!(complete("jottem edit --"), None);
+    }
+
+    #[test]
+    fn complete_tags() {
+        let tags = Some((Completion::Tags, String::new()));
+
+        assert_eq!(complete("jottem tag add foo _"), tags);
+        assert_eq!(complete("jottem tag remove foo a b"), tags);
+        assert_eq!(complete("jottem tag add -t _"), tags);
+        assert_eq!(complete("jottem create foo -t _"), tags);
+        assert_eq!(complete("jottem find -t a,b"), tags);
+        assert_eq!(complete("jottem tag rename pro"), tags);
+        assert_eq!(complete("jottem tag rename project _"), None);
+        assert_eq!(
+            complete("jottem find --tags=a"),
+            Some((Completion::Tags, "--tags=".into()))
+        );
+    }
+
+    #[test]
+    fn complete_folders() {
+        let folders = Some((Completion::Folders, String::new()));
+
+        assert_eq!(complete("jottem move-folder pro"), folders);
+        assert_eq!(complete("jottem delete-folder _"), folders);
+        assert_eq!(complete("jottem tree _"), folders);
+        assert_eq!(complete("jottem find --in pro"), folders);
+        assert_eq!(complete("jottem move-folder projects _"), None);
+    }
+}
@@ -27,3 +27,48 @@ pub fn get_db_path() -> String {
 pub fn get_editor() -> String {
     std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string())
 }
+
+/// Relative path of the note used to collect quick thoughts from `jottem jot`.
+pub fn get_inbox() -> String {
+    std::env::var("JOTTEM_INBOX").unwrap_or_else(|_| "inbox".to_string())
+}
+
+/// Picker used to choose between notes: `fzf`, `builtin` or `auto`.
+///
+/// `auto` (the default) uses fzf, which can show a preview of each note,
+/// if it is installed and falls back to the builtin picker otherwise.
+pub fn get_picker() -> String {
+    std::env::var("JOTTEM_PICKER").unwrap_or_else(|_| "auto".to_string())
+}
+
+/// How tags are cased: `lower` (the default) or `preserve`.
+pub fn get_tag_case() -> String {
+    std::env::var("JOTTEM_TAG_CASE").unwrap_or_else(|_| "lower".to_string())
+}
+
+/// Characters allowed in tags besides letters and numbers.
+///
+/// `/` separates the levels of hierarchical tags, so removing it from the
+/// list disables them.
+pub fn get_tag_chars() -> String {
+    std::env::var("JOTTEM_TAG_CHARS").unwrap_or_else(|_| "-_/".to_string())
+}
+
+/// Comma-separated origins allowed to call `jottem serve` from a browser
+/// when `--allow-origin` isn't given.
+pub fn get_api_origins() -> Vec<String> {
+    std::env::var("JOTTEM_API_ORIGINS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Token required by `jottem serve` when `--token` isn't given.
+pub fn get_api_token() -> Option<String> {
+    std::env::var("JOTTEM_API_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty())
+}
@@ -1,6 +1,10 @@
-use std::{fs::OpenOptions, io, path::Path};
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+};
 
-use crate::path::NotePath;
+use crate::{config, path::NotePath};
 
 pub fn create_file(note_path: &NotePath) -> anyhow::Result<()> {
     let path = note_path.absolute_path_with_ext();
@@ -35,22 +39,7 @@ pub fn delete_file(note_path: &NotePath) -> anyhow::Result<()> {
         )
     })?;
 
-    if note_path.has_parent() {
-        let entries: Vec<_> = std::fs::read_dir(note_path.absolute_parent().unwrap())?
-            .collect::<Result<_, io::Error>>()?;
-
-        if entries.is_empty() {
-            let path = note_path.absolute_parent().unwrap();
-            let path = Path::new(&path);
-
-            std::fs::remove_dir(path).map_err(|e| {
-                anyhow::anyhow!(
-                    "Failed to remove empty directory: {}: {e}",
-                    path.to_string_lossy()
-                )
-            })?;
-        }
-    }
+    remove_empty_parents(note_path)?;
 
     Ok(())
 }
@@ -82,6 +71,35 @@ pub fn move_file(note_path: &NotePath, new_path: &NotePath) -> anyhow::Result<()
     Ok(())
 }
 
+/// Appends text to the end of a note, starting on a new line if the note
+/// doesn't already end with one.
+pub fn append_to_file(note_path: &NotePath, contents: &str) -> anyhow::Result<()> {
+    let path = note_path.absolute_path_with_ext();
+    let path = Path::new(&path);
+
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| {
+            anyhow::anyhow!("Failed to open note file: {}: {e}", path.to_string_lossy())
+        })?;
+
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        file.write_all(b"\n")?;
+    }
+
+    file.write_all(contents.as_bytes())?;
+
+    if !contents.ends_with('\n') {
+        file.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
 pub fn exists(note_path: &NotePath) -> bool {
     let path = note_path.absolute_path_with_ext();
     let path = Path::new(&path);
@@ -89,6 +107,41 @@ pub fn exists(note_path: &NotePath) -> bool {
     path.exists()
 }
 
+/// Removes the parent directories of a note that no longer contain anything,
+/// walking upwards until a non-empty directory or the root is reached.
+pub fn remove_empty_parents(note_path: &NotePath) -> anyhow::Result<()> {
+    let root = config::get_root();
+    let root = Path::new(&root);
+
+    let Some(parent) = note_path.absolute_parent() else {
+        return Ok(());
+    };
+
+    let mut dir = Path::new(&parent);
+
+    while dir.starts_with(root) && dir != root && dir.exists() {
+        let entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, io::Error>>()?;
+
+        if !entries.is_empty() {
+            break;
+        }
+
+        std::fs::remove_dir(dir).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to remove empty directory: {}: {e}",
+                dir.to_string_lossy()
+            )
+        })?;
+
+        match dir.parent() {
+            Some(path) => dir = path,
+            None => break,
+        }
+    }
+
+    Ok(())
+}
+
 fn create_parent_path(note_path: &NotePath) -> anyhow::Result<()> {
     if !note_path.has_parent() {
         return Ok(());
@@ -182,4 +235,37 @@ mod test {
         let meta = metadata(tmp.path().join("deep/parent/path"));
         assert!(meta.is_err()); // should be a NotFound error
     }
+
+    #[test]
+    #[serial]
+    fn test_append_to_file() {
+        let tmp = setup();
+
+        let path = NotePath::parse("test").expect("Failed to parse NotePath from str");
+        create_file(&path).expect("Failed to create test file inside tmp directory");
+
+        append_to_file(&path, "one").expect("Failed to append to test file");
+        append_to_file(&path, "two\n").expect("Failed to append to test file");
+
+        let contents = std::fs::read_to_string(tmp.path().join("test.md")).unwrap();
+        assert_eq!(contents, "one\ntwo\n");
+    }
+
+    #[test]
+    #[serial]
+    fn test_remove_empty_parents_recursively() {
+        let tmp = setup();
+
+        let path = NotePath::parse("deep/parent/path/test").expect("Failed to parse NotePath");
+        create_file(&path).expect("Failed to create test file inside tmp directory");
+
+        // Every directory between the note and the root is now empty and should be removed
+        delete_file(&path).expect("Failed to delete test file");
+
+        let meta = metadata(tmp.path().join("deep"));
+        assert!(meta.is_err()); // should be a NotFound error
+
+        let meta = metadata(tmp.path()).expect("root");
+        assert!(meta.is_dir());
+    }
 }
@@ -0,0 +1,227 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local};
+use colored::Colorize;
+
+use crate::{config, file, index::Index, markdown, note::Note, path::NotePath, tags, utils};
+
+/// The outcome of an import.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+/// Imports a directory of markdown files (such as an Obsidian vault).
+///
+/// * `dir` - the directory to import
+/// * `folder` - optional folder inside the root to import into
+/// * `in_place` - adopt files that already live inside the root instead of copying them
+///
+/// Tags are taken from the `tags` (or `tag`) frontmatter key, and from any
+/// `#tags` in the body as inline tags. Tags that aren't valid are dropped.
+/// The created and modified times of each note are taken from its file.
+/// Hidden files and folders (such as `.obsidian`) and anything that isn't
+/// a `.md` file are ignored. Files that would replace an existing note, or
+/// that can't be read, are reported and skipped.
+pub fn import(dir: &Path, folder: Option<&str>, in_place: bool) -> anyhow::Result<Summary> {
+    let folder = folder.map(utils::parse_folder).transpose()?;
+
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("Failed to read directory: {}: {e}", dir.display()))?;
+
+    // notes adopted in place keep their path relative to the root
+    let base = if in_place {
+        let root = Path::new(&config::get_root()).canonicalize()?;
+
+        if !dir.starts_with(&root) {
+            return Err(anyhow::anyhow!(
+                "Error: {} is not inside the notes directory ({})",
+                dir.display(),
+                root.display()
+            ));
+        }
+
+        root
+    } else {
+        dir.clone()
+    };
+
+    let mut files = Vec::new();
+    collect_files(&dir, &mut files)?;
+    files.sort();
+
+    let index = Index::open()?;
+    let mut summary = Summary::default();
+
+    for source in files {
+        let relative = source
+            .strip_prefix(&base)?
+            .with_extension("")
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/");
+
+        let relative = match &folder {
+            Some(folder) if !in_place => format!("{folder}/{relative}"),
+            _ => relative,
+        };
+
+        let path = match NotePath::parse_contained(&relative) {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("{}", format!("Skipping {relative}: {e}").bright_yellow());
+                summary.skipped += 1;
+                continue;
+            }
+        };
+
+        if !index.find_by_path(&path)?.is_empty() || (!in_place && file::exists(&path)) {
+            eprintln!(
+                "{}",
+                format!(
+                    "Skipping {}: a note already exists there",
+                    path.relative_path()
+                )
+                .bright_yellow()
+            );
+            summary.skipped += 1;
+            continue;
+        }
+
+        let note = match import_file(&source, &path, in_place) {
+            Ok(note) => note,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Skipping {}: {e}", path.relative_path()).bright_yellow()
+                );
+                summary.skipped += 1;
+                continue;
+            }
+        };
+
+        index.insert(&note)?;
+
+        println!("{}", note.relative_path);
+        summary.imported += 1;
+    }
+
+    Ok(summary)
+}
+
+/// Creates a note from a single markdown file, copying it into the root
+/// unless it is being adopted in place.
+pub(crate) fn import_file(source: &Path, path: &NotePath, in_place: bool) -> anyhow::Result<Note> {
+    let content = std::fs::read_to_string(source)
+        .map_err(|e| anyhow::anyhow!("Failed to read file: {}: {e}", source.display()))?;
+
+    let metadata = std::fs::metadata(source)?;
+    let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+    let created = metadata.created().unwrap_or(modified);
+
+    if !in_place {
+        let destination = path.absolute_path_with_ext();
+
+        if let Some(parent) = Path::new(&destination).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::copy(source, &destination).map_err(|e| {
+            anyhow::anyhow!("Failed to copy {} to {destination}: {e}", source.display())
+        })?;
+
+        File::options()
+            .write(true)
+            .open(&destination)?
+            .set_modified(modified)?;
+    }
+
+    let (frontmatter, _) = markdown::split_frontmatter(&content);
+
+    let tags = frontmatter
+        .map(frontmatter_tags)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|tag| tags::normalize(tag).ok())
+        .collect::<Vec<String>>();
+
+    let mut note = Note::new(path, &tags);
+    note.sync_content(&content);
+    note.created = format_time(created);
+    note.modified = format_time(modified);
+
+    Ok(note)
+}
+
+/// Recursively collects markdown files, skipping hidden files and folders.
+pub(crate) fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("Failed to read directory: {}: {e}", dir.display()))?;
+
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        // symlinked folders are skipped so we can't loop forever
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads tags from YAML frontmatter.
+///
+/// Both `tags` and `tag` are accepted, either as a list or as a single
+/// string separated by commas or spaces. A leading `#` is removed.
+pub fn frontmatter_tags(frontmatter: &str) -> Vec<String> {
+    markdown::frontmatter_values(frontmatter, &["tags", "tag"])
+        .iter()
+        .flat_map(|tags| tags.split(|c: char| c == ',' || c.is_whitespace()))
+        .map(|t| t.trim().trim_start_matches('#').to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+pub(crate) fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_frontmatter_tags() {
+        assert_eq!(
+            frontmatter_tags("title: foo\ntags: [work, '#ideas']\n"),
+            vec!["work", "ideas"]
+        );
+        assert_eq!(
+            frontmatter_tags("tags:\n  - a\n  - b/c\n"),
+            vec!["a", "b/c"]
+        );
+        assert_eq!(
+            frontmatter_tags("tag: one, two three"),
+            vec!["one", "two", "three"]
+        );
+        assert!(frontmatter_tags("title: foo").is_empty());
+        assert!(frontmatter_tags("not: [valid").is_empty());
+    }
+}
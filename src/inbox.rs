@@ -0,0 +1,233 @@
+use colored::Colorize;
+
+use crate::{
+    config, file,
+    index::Index,
+    markdown,
+    note::Note,
+    path::NotePath,
+    prompt::{self, InboxAction},
+    utils,
+};
+
+/// The contents of the inbox note, split into individual entries.
+///
+/// * `header` - any frontmatter and text before the first entry (such as a heading)
+/// * `entries` - top-level list items, each including its continuation lines
+#[derive(Debug, Default, PartialEq)]
+pub struct Entries {
+    pub header: String,
+    pub entries: Vec<String>,
+}
+
+impl Entries {
+    /// Splits the inbox note content into entries.
+    ///
+    /// An entry starts at a top-level `-` or `*` list item and includes every
+    /// following line up until the next top-level list item. Frontmatter is
+    /// always kept in the header, even if it has top-level lists.
+    pub fn parse(content: &str) -> Self {
+        let (_, body) = markdown::split_frontmatter(content);
+        let mut inbox = Self {
+            header: content[..content.len() - body.len()].to_string(),
+            ..Self::default()
+        };
+
+        for line in body.lines() {
+            let is_entry = line.starts_with("- ") || line.starts_with("* ");
+
+            match inbox.entries.last_mut() {
+                Some(entry) if !is_entry => {
+                    entry.push('\n');
+                    entry.push_str(line);
+                }
+                _ if is_entry => inbox.entries.push(line.to_string()),
+                _ => {
+                    inbox.header.push_str(line);
+                    inbox.header.push('\n');
+                }
+            }
+        }
+
+        for entry in &mut inbox.entries {
+            entry.truncate(entry.trim_end().len());
+        }
+
+        inbox
+    }
+
+    /// Joins the entries back into note content.
+    pub fn render(&self) -> String {
+        let mut content = self.header.clone();
+
+        for entry in &self.entries {
+            content.push_str(entry);
+            content.push('\n');
+        }
+
+        content
+    }
+}
+
+/// Formats a line of text as a timestamped inbox entry.
+pub fn format_entry(text: &str) -> String {
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M");
+    let text = text.lines().map(str::trim).collect::<Vec<&str>>().join(" ");
+
+    format!("- [{timestamp}] {}", text.trim())
+}
+
+/// Finds the inbox note (see [config::get_inbox]), creating it if it doesn't exist yet.
+pub fn get_inbox() -> anyhow::Result<Note> {
+    let path = NotePath::parse(&config::get_inbox())?;
+    let mut matches = Index::open()?.find_by_path(&path)?;
+
+    match matches.pop() {
+        Some(note) => Ok(note),
+        None => utils::create_note(&path, &[]),
+    }
+}
+
+/// Appends a timestamped entry to the inbox note without opening the editor.
+///
+/// * `text` - the thought to jot down
+pub fn jot(text: &str) -> anyhow::Result<()> {
+    if text.trim().is_empty() {
+        return Err(anyhow::anyhow!("Error: Nothing to jot down"));
+    }
+
+    let mut inbox = get_inbox()?;
+
+    file::append_to_file(&NotePath::from_note(&inbox)?, &format_entry(text))?;
+    utils::sync_note(&mut inbox)?;
+    inbox.touch();
+
+    let index = Index::open()?;
+    index.insert(&inbox)?;
+
+    Ok(())
+}
+
+/// Walks through each inbox entry, asking the user whether to move it into
+/// a new or existing note, keep it in the inbox or delete it.
+///
+/// Entries that are moved or deleted are removed from the inbox note right
+/// away, so stopping partway (or cancelling a prompt) never leaves an entry
+/// in both the inbox and another note.
+pub fn process() -> anyhow::Result<()> {
+    let mut inbox = get_inbox()?;
+    let content = std::fs::read_to_string(&inbox.absolute_path)?;
+    let Entries { header, entries } = Entries::parse(&content);
+
+    if entries.is_empty() {
+        println!("{}", "Inbox is empty".bright_green());
+        return Ok(());
+    }
+
+    let mut kept = Vec::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        println!("\n{entry}\n");
+
+        match prompt::inbox_action()? {
+            InboxAction::NewNote => {
+                let path = prompt::input("New note path")?;
+                let path = NotePath::parse(&path)?;
+
+                if file::exists(&path) {
+                    return Err(anyhow::anyhow!(
+                        "A note already exists at {}",
+                        path.relative_path()
+                    ));
+                }
+
+                let note = utils::create_note(&path, &[])?;
+                move_entry(note, entry)?;
+            }
+            InboxAction::ExistingNote => {
+                let mut notes = Index::open()?.get_all()?;
+                notes.retain(|n| n.id() != inbox.id());
+
+                move_entry(utils::select_note(notes)?, entry)?;
+            }
+            InboxAction::Keep => {
+                kept.push(entry.clone());
+                continue;
+            }
+            InboxAction::Delete => {}
+            InboxAction::Stop => break,
+        }
+
+        let remaining = Entries {
+            header: header.clone(),
+            entries: kept.iter().chain(&entries[i + 1..]).cloned().collect(),
+        };
+
+        save_inbox(&mut inbox, &remaining)?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites the inbox note with the given entries and updates the index.
+fn save_inbox(inbox: &mut Note, entries: &Entries) -> anyhow::Result<()> {
+    std::fs::write(&inbox.absolute_path, entries.render())?;
+    utils::sync_note(inbox)?;
+    inbox.touch();
+
+    let index = Index::open()?;
+    index.insert(inbox)?;
+
+    Ok(())
+}
+
+/// Appends an inbox entry to a note and updates its modified time.
+fn move_entry(mut note: Note, entry: &str) -> anyhow::Result<()> {
+    file::append_to_file(&NotePath::from_note(&note)?, entry)?;
+    utils::sync_note(&mut note)?;
+    note.touch();
+
+    let index = Index::open()?;
+    index.insert(&note)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_inbox_entries() {
+        let content = "# Inbox\n\n- one\n  more of one\n* two\n\n- three\n";
+        let inbox = Entries::parse(content);
+
+        assert_eq!(inbox.header, "# Inbox\n\n");
+        assert_eq!(
+            inbox.entries,
+            vec!["- one\n  more of one", "* two", "- three"]
+        );
+        assert_eq!(
+            inbox.render(),
+            "# Inbox\n\n- one\n  more of one\n* two\n- three\n"
+        );
+    }
+
+    #[test]
+    fn parse_inbox_entries_with_frontmatter() {
+        let content = "---\ntags:\n- foo\n- bar\n---\n- one\n";
+        let inbox = Entries::parse(content);
+
+        assert_eq!(inbox.header, "---\ntags:\n- foo\n- bar\n---\n");
+        assert_eq!(inbox.entries, vec!["- one"]);
+        assert_eq!(inbox.render(), content);
+    }
+
+    #[test]
+    fn format_inbox_entry() {
+        let entry = format_entry("  buy\nmilk ");
+
+        assert!(entry.starts_with("- ["));
+        assert!(entry.ends_with("] buy milk"));
+    }
+}
@@ -1,8 +1,39 @@
-use std::{collections::HashSet, path::Path};
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
 
-use rocksdb::{Options, DB};
+use glob::{MatchOptions, Pattern};
+use rocksdb::{ColumnFamily, Direction, IteratorMode, Options, DB};
 
-use crate::{config, note::Note, path::NotePath};
+use crate::{cli::SearchArgs, config, note::Note, path::NotePath};
+
+/// How long to wait for another jottem command (such as `jottem watch`)
+/// to close the index before giving up.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Column family mapping each note's relative path to its id, so notes
+/// can be looked up by path (or path prefix) without reading every record.
+const PATHS: &str = "paths";
+
+/// Column family mapping the name of each saved search to its JSON
+/// serialized [SearchArgs].
+const SEARCHES: &str = "searches";
+
+/// Column family for information about the index itself, such as
+/// [RECORDS_VERSIONED].
+const META: &str = "meta";
+
+/// Set once every note record has been rewritten in the versioned format
+/// (see [Note::serialize]).
+const RECORDS_VERSIONED: &str = "records_versioned";
+
+/// How glob patterns match relative paths: `*` and `?` never match a `/`.
+pub(crate) const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
 
 #[derive(Debug)]
 pub struct Index {
@@ -10,29 +41,132 @@ pub struct Index {
 }
 
 impl Index {
+    /// Opens the index, waiting (up to [LOCK_TIMEOUT]) while another jottem
+    /// command has it open.
     pub fn open() -> anyhow::Result<Self> {
         let db_path = config::get_db_path();
         let db_path = Path::new(&db_path);
         let mut options = Options::default();
         options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let start = Instant::now();
+
+        let db = loop {
+            match DB::open_cf(&options, db_path, [PATHS, SEARCHES, META]) {
+                Ok(db) => break db,
+                // RocksDB only allows one process to open a database at a time
+                Err(e) if e.to_string().contains("lock") && start.elapsed() < LOCK_TIMEOUT => {
+                    std::thread::sleep(Duration::from_millis(50))
+                }
+                Err(e) => return Err(anyhow::anyhow!("Failed to open database: {e}")),
+            }
+        };
+
+        let index = Self { db };
+        index.migrate_records()?;
+        index.reindex_paths()?;
+
+        Ok(index)
+    }
+
+    fn paths(&self) -> anyhow::Result<&ColumnFamily> {
+        self.db
+            .cf_handle(PATHS)
+            .ok_or_else(|| anyhow::anyhow!("Failed to open the path index"))
+    }
+
+    fn searches(&self) -> anyhow::Result<&ColumnFamily> {
+        self.db
+            .cf_handle(SEARCHES)
+            .ok_or_else(|| anyhow::anyhow!("Failed to open the saved searches"))
+    }
+
+    fn meta(&self) -> anyhow::Result<&ColumnFamily> {
+        self.db
+            .cf_handle(META)
+            .ok_or_else(|| anyhow::anyhow!("Failed to open the index metadata"))
+    }
+
+    /// Rewrites note records from before they were versioned, once per
+    /// database.
+    fn migrate_records(&self) -> anyhow::Result<()> {
+        let versioned = self
+            .db
+            .get_cf(self.meta()?, RECORDS_VERSIONED)
+            .map_err(|e| anyhow::anyhow!("Failed to retrieve database record: {e}"))?
+            .is_some();
+
+        if versioned {
+            return Ok(());
+        }
+
+        let records = self
+            .db
+            .iterator(IteratorMode::Start)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!("Failed to read database record: {e}"))?;
 
-        let db = DB::open(&options, db_path)
-            .map_err(|e| anyhow::anyhow!("Failed to open database: {e}"))?;
+        for (id, record) in records {
+            let (_, record) = Note::deserialize_unversioned(&record)?.serialize()?;
 
-        Ok(Self { db })
+            self.db
+                .put(id, record)
+                .map_err(|e| anyhow::anyhow!("Failed to migrate note: {e}"))?;
+        }
+
+        self.db
+            .put_cf(self.meta()?, RECORDS_VERSIONED, [1])
+            .map_err(|e| anyhow::anyhow!("Failed to migrate the index: {e}"))
+    }
+
+    /// Fills in the path index for databases created before it existed.
+    fn reindex_paths(&self) -> anyhow::Result<()> {
+        let indexed = self
+            .db
+            .iterator_cf(self.paths()?, IteratorMode::Start)
+            .next()
+            .is_some();
+
+        if indexed {
+            return Ok(());
+        }
+
+        for note in self.get_all()? {
+            self.insert_path(&note)?;
+        }
+
+        Ok(())
     }
 
     pub fn insert(&self, note: &Note) -> anyhow::Result<()> {
-        let (id, note) = note.serialize()?;
+        let (id, record) = note.serialize()?;
 
         self.db
-            .put(id.to_le_bytes(), note)
+            .put(id.to_le_bytes(), record)
             .map_err(|e| anyhow::anyhow!("Failed to insert note into the index: {e}"))?;
 
-        Ok(())
+        self.insert_path(note)
+    }
+
+    fn insert_path(&self, note: &Note) -> anyhow::Result<()> {
+        self.db
+            .put_cf(self.paths()?, &note.relative_path, note.id().to_le_bytes())
+            .map_err(|e| anyhow::anyhow!("Failed to insert note into the path index: {e}"))
     }
 
     pub fn remove(&self, id: u64) -> anyhow::Result<()> {
+        // a moved note's new record may already own its old path
+        if let Some(note) = self.get(id)? {
+            if self.get_id(&note.relative_path)? == Some(id) {
+                self.db
+                    .delete_cf(self.paths()?, &note.relative_path)
+                    .map_err(|e| {
+                        anyhow::anyhow!("Failed to remove note from the path index: {e}")
+                    })?;
+            }
+        }
+
         self.db
             .delete(id.to_le_bytes())
             .map_err(|e| anyhow::anyhow!("Failed to remove note from the index: {e}"))?;
@@ -70,32 +204,130 @@ impl Index {
         Ok(notes)
     }
 
+    /// Looks up the id of the note at a relative path in the path index.
+    fn get_id(&self, relative_path: &str) -> anyhow::Result<Option<u64>> {
+        let record = self
+            .db
+            .get_cf(self.paths()?, relative_path)
+            .map_err(|e| anyhow::anyhow!("Failed to retrieve database record: {e}"))?;
+
+        Ok(record
+            .and_then(|id| id.as_slice().try_into().ok())
+            .map(u64::from_le_bytes))
+    }
+
+    /// Iterates the path index from `prefix`, returning the relative path
+    /// and id of every note whose path starts with it, in path order.
+    fn find_ids_by_prefix(&self, prefix: &str) -> anyhow::Result<Vec<(String, u64)>> {
+        let mut ids = Vec::new();
+        let mode = IteratorMode::From(prefix.as_bytes(), Direction::Forward);
+
+        for record in self.db.iterator_cf(self.paths()?, mode) {
+            let (path, id) =
+                record.map_err(|e| anyhow::anyhow!("Failed to read database record: {e}"))?;
+
+            if !path.starts_with(prefix.as_bytes()) {
+                break;
+            }
+
+            let path = String::from_utf8_lossy(&path).to_string();
+
+            if let Ok(id) = id.as_ref().try_into() {
+                ids.push((path, u64::from_le_bytes(id)));
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Reads the notes for the ids of [find_ids_by_prefix](Self::find_ids_by_prefix),
+    /// keeping those whose relative path passes `filter`.
+    fn get_by_paths(
+        &self,
+        ids: Vec<(String, u64)>,
+        filter: impl Fn(&str) -> bool,
+    ) -> anyhow::Result<Vec<Note>> {
+        let mut notes = Vec::new();
+
+        for (_, id) in ids.into_iter().filter(|(path, _)| filter(path)) {
+            if let Some(note) = self.get(id)? {
+                notes.push(note);
+            }
+        }
+
+        Ok(notes)
+    }
+
+    /// Finds notes by title, treating aliases as alternative titles.
     pub fn find_by_title(&self, title: &str) -> anyhow::Result<Vec<Note>> {
         Ok(self
             .get_all()?
             .into_iter()
-            .filter(|note| note.title == title)
+            .filter(|note| note.has_title(title))
             .collect())
     }
 
     pub fn find_by_path(&self, path: &NotePath) -> anyhow::Result<Vec<Note>> {
-        Ok(self
-            .get_all()?
-            .into_iter()
-            .filter(|note| note.relative_path == path.relative_path())
-            .collect())
+        let note = match self.get_id(&path.relative_path())? {
+            Some(id) => self.get(id)?,
+            None => None,
+        };
+
+        Ok(note.into_iter().collect())
     }
 
-    pub fn find_by_tags(&self, tags: &[String]) -> anyhow::Result<Vec<Note>> {
-        let tags: HashSet<_> = tags.iter().collect();
+    /// Finds notes the way users refer to them: by relative path if the
+    /// input has a parent folder, or by title otherwise. Aliases are treated
+    /// as alternative titles in both cases.
+    pub fn find(&self, path: &NotePath) -> anyhow::Result<Vec<Note>> {
+        if !path.has_parent() {
+            return self.find_by_title(&path.title);
+        }
 
+        let matches = self.find_by_path(path)?;
+
+        if matches.is_empty() {
+            self.find_by_alias(path)
+        } else {
+            Ok(matches)
+        }
+    }
+
+    /// Finds notes in the same folder as `path` with an alias matching its
+    /// title, so `foo/alias` finds the note `foo/bar` if it has that alias.
+    pub fn find_by_alias(&self, path: &NotePath) -> anyhow::Result<Vec<Note>> {
         Ok(self
             .get_all()?
             .into_iter()
-            .filter(|n| n.tags.iter().any(|s| tags.contains(s)))
+            .filter(|note| note.aliases.contains(&path.title))
+            .filter(|note| NotePath::from_note(note).is_ok_and(|p| p.parent == path.parent))
             .collect())
     }
 
+    /// Finds every note stored under a folder, including nested subfolders.
+    ///
+    /// * `folder` - relative folder path such as `foo/bar`
+    pub fn find_by_folder(&self, folder: &str) -> anyhow::Result<Vec<Note>> {
+        let prefix = format!("{}/", folder.trim_matches('/'));
+        let ids = self.find_ids_by_prefix(&prefix)?;
+
+        self.get_by_paths(ids, |_| true)
+    }
+
+    /// Finds notes whose relative path matches a glob pattern such as
+    /// `projects/*/todo`.
+    ///
+    /// `*` and `?` never match a `/`, but `**` matches any number of folders.
+    /// Only the paths starting with the literal part of the pattern are read.
+    pub fn find_by_glob(&self, pattern: &str) -> anyhow::Result<Vec<Note>> {
+        let glob = Pattern::new(pattern)
+            .map_err(|e| anyhow::anyhow!("Error: Invalid glob pattern \"{pattern}\": {e}"))?;
+        let literal = pattern.find(['*', '?', '[']).unwrap_or(pattern.len());
+        let ids = self.find_ids_by_prefix(&pattern[..literal])?;
+
+        self.get_by_paths(ids, |path| glob.matches_with(path, GLOB_OPTIONS))
+    }
+
     pub fn add_tags(&self, id: u64, tags: &[String]) -> anyhow::Result<()> {
         if let Some(mut note) = self.get(id)? {
             note.add_tags(tags);
@@ -113,4 +345,50 @@ impl Index {
 
         Ok(())
     }
+
+    /// Saves search parameters under a name, replacing any existing search
+    /// with that name.
+    pub fn save_search(&self, name: &str, args: &SearchArgs) -> anyhow::Result<()> {
+        let record = serde_json::to_vec(args)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize search: {e}"))?;
+
+        self.db
+            .put_cf(self.searches()?, name, record)
+            .map_err(|e| anyhow::anyhow!("Failed to save search: {e}"))
+    }
+
+    pub fn get_search(&self, name: &str) -> anyhow::Result<Option<SearchArgs>> {
+        let record = self
+            .db
+            .get_cf(self.searches()?, name)
+            .map_err(|e| anyhow::anyhow!("Failed to retrieve database record: {e}"))?;
+
+        record
+            .map(|record| serde_json::from_slice(&record))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize search: {e}"))
+    }
+
+    /// Returns every saved search, sorted by name.
+    pub fn get_searches(&self) -> anyhow::Result<Vec<(String, SearchArgs)>> {
+        let mut searches = Vec::new();
+
+        for record in self.db.iterator_cf(self.searches()?, IteratorMode::Start) {
+            let (name, record) =
+                record.map_err(|e| anyhow::anyhow!("Failed to read database record: {e}"))?;
+
+            let args = serde_json::from_slice(&record)
+                .map_err(|e| anyhow::anyhow!("Failed to deserialize search: {e}"))?;
+
+            searches.push((String::from_utf8_lossy(&name).to_string(), args));
+        }
+
+        Ok(searches)
+    }
+
+    pub fn delete_search(&self, name: &str) -> anyhow::Result<()> {
+        self.db
+            .delete_cf(self.searches()?, name)
+            .map_err(|e| anyhow::anyhow!("Failed to delete search: {e}"))
+    }
 }
@@ -1,41 +1,92 @@
-use cli::{SearchArgs, TagCommand};
+use cli::{
+    AliasCommand, InboxCommand, OutputArgs, SearchArgs, SearchCommand, ShowArgs, TagCommand,
+    TreeArgs,
+};
 use colored::Colorize;
 use index::Index;
 use path::NotePath;
 
+pub mod aliases;
+pub mod backup;
 pub mod cli;
+pub mod completions;
 pub mod config;
 pub mod file;
+pub mod import;
+pub mod inbox;
 pub mod index;
+pub mod man;
+pub mod markdown;
 pub mod note;
 pub mod path;
 pub mod prompt;
+pub mod search;
+pub mod server;
+pub mod site;
 pub mod tags;
+pub mod tree;
+pub mod tui;
 pub mod utils;
+pub mod watch;
 
 /// Opens a note in the user's editor per the $EDITOR variable
 ///
-/// * `path` - raw input from the user such as `foo/bar`
+/// * `path` - raw input from the user such as `foo/bar`. If omitted,
+///   the user chooses a note with fuzzy finding.
 ///
-/// After the editor is closed, we update the modified time on the note
-/// and then update the record in the index.
+/// After the editor is closed, we update the modified time, inline tags
+/// and aliases on the note and then update the record in the index.
 pub fn edit_note(path: Option<String>) -> anyhow::Result<()> {
-    let mut note = if path.is_none() {
-        let mut notes = Index::open()?.get_all()?;
-        let options = notes
-            .iter()
-            .map(|n| n.relative_path.as_str())
-            .collect::<Vec<&str>>();
-
-        let selection = prompt::select_fuzzy(&options)?;
-        notes.swap_remove(selection)
-    } else {
-        let path = path.unwrap();
-        utils::get_note(&path, true)?
-    };
+    let note = utils::get_or_select_note(path.as_deref(), true)?;
+
+    open_and_sync(note)
+}
+
+/// Creates a note and opens it in the editor, or just opens it if a note
+/// already exists at exactly that path.
+///
+/// * `path` - raw input from the user such as `foo/bar`
+/// * `tags` - tags to add to the note
+///
+/// Unlike [edit_note], the input is always treated as a path, so other
+/// notes with the same title or alias don't get in the way.
+pub fn create_note(path: &str, tags: &[String]) -> anyhow::Result<()> {
+    let note = get_or_create_at_path(path, tags)?;
+
+    open_and_sync(note)
+}
 
+/// Finds the note at exactly the given path, creating it if there is none.
+///
+/// The tags are added to an existing note as well as to a new one.
+fn get_or_create_at_path(path: &str, tags: &[String]) -> anyhow::Result<note::Note> {
+    let path = NotePath::parse(path)?;
+    let existing = Index::open()?.find_by_path(&path)?.pop();
+
+    match existing {
+        Some(mut note) => {
+            let tags = tags::normalize_all(tags)?;
+
+            if !tags.is_empty() {
+                note.add_tags(&tags);
+                Index::open()?.insert(&note)?;
+            }
+
+            Ok(note)
+        }
+        None if file::exists(&path) => Err(anyhow::anyhow!(
+            "Error: {} exists but isn't indexed (try `jottem import --in-place <dir>`)",
+            path.absolute_path_with_ext()
+        )),
+        None => utils::create_note(&path, tags),
+    }
+}
+
+/// Opens a note in the editor, then syncs its index record if it changed.
+fn open_and_sync(mut note: note::Note) -> anyhow::Result<()> {
     if utils::open_note(&note.absolute_path)? {
-        note.modified = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        utils::sync_note(&mut note)?;
+        note.touch();
 
         let index = Index::open()?;
         index.insert(&note)?;
@@ -44,56 +95,178 @@ pub fn edit_note(path: Option<String>) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Writes text piped through stdin into the note at exactly the given path,
+/// without opening the editor.
+///
+/// * `path` - raw input from the user such as `foo/bar`
+/// * `tags` - tags to add to the note
+///
+/// Like [create_note], the note (and its index record) is created if it
+/// doesn't exist yet, and is appended to otherwise.
+pub fn create_note_from_stdin(path: &str, tags: &[String]) -> anyhow::Result<()> {
+    let input = utils::read_stdin()?;
+    let note = get_or_create_at_path(path, tags)?;
+
+    append_and_sync(note, &input)
+}
+
+/// Appends text piped through stdin to a note without opening the editor.
+///
+/// * `path` - raw input from the user such as `foo/bar`
+///
+/// The note (and its index record) is created if no existing note matches
+/// the input. The modified time of the note is updated afterwards.
+pub fn append_note(path: &str) -> anyhow::Result<()> {
+    let input = utils::read_stdin()?;
+    let note = utils::get_or_create_note(path)?;
+
+    append_and_sync(note, &input)
+}
+
+/// Appends text to a note, then updates its index record.
+fn append_and_sync(mut note: note::Note, input: &str) -> anyhow::Result<()> {
+    file::append_to_file(&NotePath::from_note(&note)?, input)?;
+    utils::sync_note(&mut note)?;
+    note.touch();
+
+    let index = Index::open()?;
+    index.insert(&note)?;
+
+    Ok(())
+}
+
+/// Prints the content of a note to stdout.
+///
+/// * `args` - Output options provided by the user. See [cli::ShowArgs]
+pub fn show_note(args: &ShowArgs) -> anyhow::Result<()> {
+    let note = utils::get_or_select_note(args.path.as_deref(), false)?;
+    let content = std::fs::read_to_string(&note.absolute_path)?;
+
+    let mut content = if args.no_frontmatter || args.section.is_some() {
+        markdown::split_frontmatter(&content).1.to_string()
+    } else {
+        content
+    };
+
+    if let Some(heading) = &args.section {
+        content = markdown::extract_section(&content, heading)
+            .ok_or_else(|| anyhow::anyhow!("No section found with heading: {heading}"))?;
+    }
+
+    if args.color {
+        content = markdown::colorize(&content);
+    }
+
+    print!("{content}");
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        println!();
+    }
+
+    Ok(())
+}
+
 /// Collects a list of notes based on user-specified search parameters.
 ///
 /// * `args` - Search parameters provided by the user. See [cli::SearchArgs]
+/// * `output` - how to print the notes. See [utils::print_notes]
+pub fn find_notes(args: &SearchArgs, output: &OutputArgs) -> anyhow::Result<()> {
+    let query = search::Query::parse(args)?;
+    let hits = query.run(&Index::open()?)?;
+
+    utils::print_notes(hits, output)
+}
+
+/// Saves, lists, runs or deletes named searches.
 ///
-/// The collected notes are displayed as an ASCII table with the relative
-/// path to the note and the last modified time.
-pub fn find_notes(args: &SearchArgs) -> anyhow::Result<()> {
+/// Saved searches store the search parameters rather than the results,
+/// so relative dates such as `7d` are relative to when the search is run.
+pub fn manage_searches(cmd: SearchCommand) -> anyhow::Result<()> {
     let index = Index::open()?;
 
-    let notes = {
-        if args.path.is_some() {
-            let path = args.path.as_ref().unwrap();
+    match cmd {
+        SearchCommand::Save { name, args } => {
+            let name = search::validate_name(&name)?;
 
-            let path: &str = path;
-            let path = NotePath::parse(path)?;
+            // catch invalid patterns or dates now rather than on every run
+            search::Query::parse(&args)?;
+            index.save_search(&name, &args)?;
 
-            if path.has_parent() {
-                index.find_by_path(&path)?
-            } else {
-                index.find_by_title(&path.title)?
+            println!("{}", format!("Saved search {name}").bright_green());
+        }
+        SearchCommand::List => {
+            for (name, args) in index.get_searches()? {
+                println!("{}  {}", name.bright_cyan(), search::describe(&args));
             }
-        } else if !args.tags.is_empty() {
-            let tags: &[String] = &args.tags;
-            index.find_by_tags(tags)?
-        } else if args.all {
-            index.get_all()?
-        } else {
-            println!("{}", "Found 0 matching notes".bright_red());
-            return Ok(());
         }
-    };
+        SearchCommand::Run { name, output } => {
+            let args = index
+                .get_search(&name)?
+                .ok_or_else(|| anyhow::anyhow!("Error: No saved search named \"{name}\""))?;
+            let hits = search::Query::parse(&args)?.run(&index)?;
 
-    let table = utils::build_table(notes);
+            utils::print_notes(hits, &output)?;
+        }
+        SearchCommand::Delete { name } => {
+            if index.get_search(&name)?.is_none() {
+                return Err(anyhow::anyhow!("Error: No saved search named \"{name}\""));
+            }
 
-    println!("{table}");
+            index.delete_search(&name)?;
+
+            println!("{}", format!("Deleted search {name}").bright_green());
+        }
+    }
 
     Ok(())
 }
 
-/// Deletes a note both from disk and the index.
+/// Displays the notes hierarchy as an indented tree.
 ///
-/// * `path` - raw input from the user such as `foo/bar`
-pub fn delete_note(path: &str) -> anyhow::Result<()> {
-    let note = utils::get_note(path, false)?;
-    let path = NotePath::from_note(&note)?;
+/// * `args` - Display parameters provided by the user. See [cli::TreeArgs]
+///
+/// If a folder is given, only the notes under that folder are shown.
+pub fn show_tree(args: &TreeArgs) -> anyhow::Result<()> {
+    let index = Index::open()?;
+    let root = tree::Folder::from_notes(index.get_all()?)?;
 
-    file::delete_file(&path)?;
+    let (name, folder) = match &args.folder {
+        Some(path) => {
+            let folder = root
+                .get(path)
+                .ok_or_else(|| anyhow::anyhow!("No folder found: {path}"))?;
+
+            (path.trim_matches('/'), folder)
+        }
+        None => (".", &root),
+    };
+
+    print!("{}", tree::render(folder, name, args));
+
+    Ok(())
+}
+
+/// Deletes notes both from disk and the index.
+///
+/// * `path` - raw input from the user such as `foo/bar`. If omitted,
+///   the user chooses any number of notes to delete.
+///
+/// The user is asked for confirmation if more than one note was chosen.
+pub fn delete_note(path: Option<&str>) -> anyhow::Result<()> {
+    let notes = utils::get_or_select_notes(path, false)?;
+
+    if notes.len() > 1 && !prompt::confirm_bulk("Delete", notes.len())? {
+        return Ok(());
+    }
 
     let index = Index::open()?;
-    index.remove(note.id())?;
+
+    for note in notes {
+        let path = NotePath::from_note(&note)?;
+
+        file::delete_file(&path)?;
+        index.remove(note.id())?;
+    }
 
     Ok(())
 }
@@ -101,8 +274,45 @@ pub fn delete_note(path: &str) -> anyhow::Result<()> {
 /// Triggers the appropriate tag management action.
 pub fn manage_tags(command: cli::TagCommand) -> anyhow::Result<()> {
     match command {
-        TagCommand::Add { path, tags } => tags::add_tags(&path, &tags)?,
-        TagCommand::Remove { path, tags } => tags::remove_tags(&path, &tags)?,
+        TagCommand::Add {
+            path,
+            mut tags,
+            tag,
+        } => {
+            tags.extend(tag);
+            tags::add_tags(path.as_deref(), &tags)?
+        }
+        TagCommand::Remove {
+            path,
+            mut tags,
+            tag,
+        } => {
+            tags.extend(tag);
+            tags::remove_tags(path.as_deref(), &tags)?
+        }
+        TagCommand::List => tags::list_tags()?,
+        TagCommand::Rename { tag, new_tag } => tags::rename_tag(&tag, &new_tag)?,
+        TagCommand::Normalize { dry_run } => tags::normalize_index(dry_run)?,
+    }
+
+    Ok(())
+}
+
+/// Opens the inbox note, or triggers the appropriate inbox action.
+pub fn manage_inbox(command: Option<InboxCommand>) -> anyhow::Result<()> {
+    match command {
+        None => open_and_sync(inbox::get_inbox()?)?,
+        Some(InboxCommand::Process) => inbox::process()?,
+    }
+
+    Ok(())
+}
+
+/// Adds or removes note aliases. See [cli::AliasCommand]
+pub fn manage_aliases(command: AliasCommand) -> anyhow::Result<()> {
+    match command {
+        AliasCommand::Add { path, alias } => aliases::add_alias(&path, &alias)?,
+        AliasCommand::Remove { path, alias } => aliases::remove_alias(&path, &alias)?,
     }
 
     Ok(())
@@ -110,14 +320,15 @@ pub fn manage_tags(command: cli::TagCommand) -> anyhow::Result<()> {
 
 /// Renames a note (changes final path segment) in place.
 ///
-/// * `path` - raw input from the user such as `foo/bar`
+/// * `path` - raw input from the user such as `foo/bar`. If omitted,
+///   the user chooses a note with fuzzy finding.
 /// * `new_title` - the new title for the note
 ///
 /// This function renames the note both on disk and index,
 /// but does not change the relative path (except for the filename).
-pub fn rename_note(path: &str, new_title: &str) -> anyhow::Result<()> {
+pub fn rename_note(path: Option<&str>, new_title: &str) -> anyhow::Result<()> {
     let new_title = new_title.replace("/", "");
-    let mut note = utils::get_note(path, false)?;
+    let mut note = utils::get_or_select_note(path, false)?;
     let id = note.id();
 
     let old_path = NotePath::from_note(&note)?;
@@ -144,25 +355,149 @@ pub fn rename_note(path: &str, new_title: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-// TODO: refactor and document this
-pub fn move_note(path: &str, new_path: &str, rename: bool) -> anyhow::Result<()> {
+/// Moves a note to a new relative path, or renames it in place.
+///
+/// * `path` - raw input from the user such as `foo/bar`. If omitted,
+///   the user chooses a note with fuzzy finding.
+/// * `new_path` - the new relative path for the note
+/// * `rename` - if true, `new_path` is the new title for the note.
+///   See [rename_note]
+pub fn move_note(path: Option<&str>, new_path: &str, rename: bool) -> anyhow::Result<()> {
     if rename {
         rename_note(path, new_path)?;
         return Ok(());
     }
 
-    let mut note = utils::get_note(path, false)?;
+    let note = utils::get_or_select_note(path, false)?;
+    let new_path = NotePath::parse(new_path)?;
 
-    let id = note.id();
+    relocate_note(note, &new_path)?;
+
+    Ok(())
+}
+
+/// Moves every note under a folder to a new folder, keeping their layout.
+///
+/// * `folder` - relative folder path such as `foo/bar`
+/// * `new_folder` - the relative folder path to move the notes into
+/// * `rename` - if true, `new_folder` is the new name for the folder
+///   and it stays in its current parent folder
+///
+/// The user is asked for confirmation if more than one note would be moved.
+/// Nothing is moved if any of the destination notes already exist. If a note
+/// fails to move, the remaining notes are still moved and every failure is
+/// reported, and an error is returned afterwards.
+pub fn move_folder(folder: &str, new_folder: &str, rename: bool) -> anyhow::Result<()> {
+    let folder = utils::parse_folder(folder)?;
+    let new_folder = if rename {
+        let name = new_folder.replace('/', "");
+        let new_folder = match folder.rsplit_once('/') {
+            Some((parent, _)) => format!("{parent}/{name}"),
+            None => name,
+        };
+
+        utils::parse_folder(&new_folder)?
+    } else {
+        utils::parse_folder(new_folder)?
+    };
+
+    if new_folder == folder || new_folder.starts_with(&format!("{folder}/")) {
+        return Err(anyhow::anyhow!(
+            "Cannot move folder {folder} into itself: {new_folder}"
+        ));
+    }
+
+    let notes = Index::open()?.find_by_folder(&folder)?;
+
+    if notes.is_empty() {
+        utils::exit_no_matches();
+    }
+
+    let mut moves = Vec::new();
+
+    for note in notes {
+        let suffix = &note.relative_path[folder.len()..];
+        let new_path = NotePath::parse(&format!("{new_folder}{suffix}"))?;
+
+        if file::exists(&new_path) {
+            return Err(anyhow::anyhow!(
+                "A note already exists at {}",
+                new_path.relative_path()
+            ));
+        }
+
+        moves.push((note, new_path));
+    }
+
+    if moves.len() > 1 && !prompt::confirm_bulk("Move", moves.len())? {
+        return Ok(());
+    }
+
+    let total = moves.len();
+    let mut failed = 0;
+
+    for (note, new_path) in moves {
+        let old_path = note.relative_path.clone();
+
+        if let Err(e) = relocate_note(note, &new_path) {
+            eprintln!(
+                "{}",
+                format!(
+                    "Failed to move {old_path} to {}: {e}",
+                    new_path.relative_path()
+                )
+                .bright_red()
+            );
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!("Failed to move {failed} of {total} notes"));
+    }
+
+    Ok(())
+}
 
+/// Deletes every note under a folder both from disk and the index.
+///
+/// * `folder` - relative folder path such as `foo/bar`
+///
+/// The user is asked for confirmation if more than one note would be deleted.
+/// Directories left empty afterwards are removed.
+pub fn delete_folder(folder: &str) -> anyhow::Result<()> {
+    let folder = utils::parse_folder(folder)?;
+    let index = Index::open()?;
+    let notes = index.find_by_folder(&folder)?;
+
+    if notes.is_empty() {
+        utils::exit_no_matches();
+    }
+
+    if notes.len() > 1 && !prompt::confirm_bulk("Delete", notes.len())? {
+        return Ok(());
+    }
+
+    for note in notes {
+        let path = NotePath::from_note(&note)?;
+
+        file::delete_file(&path)?;
+        index.remove(note.id())?;
+    }
+
+    Ok(())
+}
+
+/// Moves a note to a new path both on disk and in the index.
+pub(crate) fn relocate_note(mut note: note::Note, new_path: &NotePath) -> anyhow::Result<()> {
+    let id = note.id();
     let old_path = NotePath::from_note(&note)?;
-    let new_path = NotePath::parse(new_path)?;
 
     note.relative_path = new_path.relative_path();
     note.absolute_path = new_path.absolute_path_with_ext();
     note.title = new_path.title.clone();
 
-    file::move_file(&old_path, &new_path)?;
+    file::move_file(&old_path, new_path)?;
 
     let index = Index::open()?;
     index.insert(&note)?;
@@ -171,6 +506,86 @@ pub fn move_note(path: &str, new_path: &str, rename: bool) -> anyhow::Result<()>
     Ok(())
 }
 
+/// Writes man pages for every command into a directory.
+///
+/// * `out_dir` - directory to write the pages into
+pub fn generate_man_pages(out_dir: &str) -> anyhow::Result<()> {
+    for page in man::generate(std::path::Path::new(out_dir))? {
+        println!("{}", page.to_string_lossy());
+    }
+
+    Ok(())
+}
+
+/// Imports a directory of markdown files as notes.
+///
+/// * `dir` - the directory to import. See [import::import]
+/// * `folder` - optional folder inside the root to import into
+/// * `in_place` - adopt files already inside the root instead of copying them
+pub fn import_notes(dir: &str, folder: Option<&str>, in_place: bool) -> anyhow::Result<()> {
+    let summary = import::import(std::path::Path::new(dir), folder, in_place)?;
+
+    println!(
+        "{}",
+        format!(
+            "Imported {} notes ({} skipped)",
+            summary.imported, summary.skipped
+        )
+        .bright_green()
+    );
+
+    Ok(())
+}
+
+/// Bundles every note into a `.tar.gz` archive. See [backup::backup]
+pub fn backup_notes(file: &str) -> anyhow::Result<()> {
+    let summary = backup::backup(std::path::Path::new(file))?;
+
+    println!(
+        "{}",
+        format!(
+            "Backed up {} notes to {file} ({} skipped)",
+            summary.backed_up, summary.skipped
+        )
+        .bright_green()
+    );
+
+    Ok(())
+}
+
+/// Restores notes from a backup archive. See [backup::restore]
+///
+/// * `file` - the archive to restore
+/// * `conflict` - what to do with notes that already exist
+pub fn restore_notes(file: &str, conflict: cli::Conflict) -> anyhow::Result<()> {
+    let summary = backup::restore(std::path::Path::new(file), conflict)?;
+
+    println!(
+        "{}",
+        format!(
+            "Restored {} notes ({} skipped)",
+            summary.restored, summary.skipped
+        )
+        .bright_green()
+    );
+
+    Ok(())
+}
+
+/// Renders every note to a static HTML site.
+///
+/// * `out_dir` - directory to write the site into. See [site::export]
+pub fn export_site(out_dir: &str) -> anyhow::Result<()> {
+    let count = site::export(std::path::Path::new(out_dir))?;
+
+    println!(
+        "{}",
+        format!("Exported {count} notes to {out_dir}").bright_green()
+    );
+
+    Ok(())
+}
+
 /// Prints out the entire index as JSON
 ///
 /// This is currently here for debugging purposes, but may serve
@@ -1,36 +1,81 @@
+use std::io::IsTerminal;
+
 use clap::Parser;
 
 use jottem::{
     cli::{Cli, Command},
-    file,
-    path::NotePath,
-    utils,
+    completions, config, prompt, utils,
 };
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    utils::create_root_dir()?;
+    prompt::set_interactive(!cli.no_input && std::io::stdin().is_terminal());
+    prompt::set_assume_yes(cli.yes);
+    utils::set_exact(cli.exact);
+
+    // completion runs on every Tab press, so it must stay cheap
+    if !matches!(cli.command, Command::Complete { .. }) {
+        utils::create_root_dir()?;
+    }
 
     match cli.command {
-        Command::Create { path, tags } => {
-            let path = NotePath::parse(&path)?;
-            if file::exists(&path) {
-                jottem::edit_note(Some(path.relative_path()))?;
-            } else {
-                let note = utils::create_note(&path, &tags)?;
-                utils::open_note(&note.absolute_path)?;
-            }
+        Command::Create { path, tags, stdin } if stdin => {
+            jottem::create_note_from_stdin(&path, &tags)?
         }
+        Command::Create { path, tags, .. } => jottem::create_note(&path, &tags)?,
+        Command::Append { path } => jottem::append_note(&path)?,
+        Command::Jot { text } => jottem::inbox::jot(&text.join(" "))?,
+        Command::Inbox { subcommand } => jottem::manage_inbox(subcommand)?,
         Command::Edit { path } => jottem::edit_note(path)?,
-        Command::Find { args } => jottem::find_notes(&args)?,
-        Command::Delete { path } => jottem::delete_note(&path)?,
+        Command::Show { args } => jottem::show_note(&args)?,
+        Command::Find { args, output } => jottem::find_notes(&args, &output)?,
+        Command::Search { subcommand } => jottem::manage_searches(subcommand)?,
+        Command::Delete { path } => jottem::delete_note(path.as_deref())?,
         Command::Tag { subcommand } => jottem::manage_tags(subcommand)?,
+        Command::Alias { subcommand } => jottem::manage_aliases(subcommand)?,
         Command::Move {
             path,
             new_path,
+            to,
+            rename,
+        } => {
+            // clap requires one of them
+            let new_path = new_path.or(to).unwrap_or_default();
+            jottem::move_note(path.as_deref(), &new_path, rename)?
+        }
+        Command::MoveFolder {
+            folder,
+            new_folder,
             rename,
-        } => jottem::move_note(&path, &new_path, rename)?,
+        } => jottem::move_folder(&folder, &new_folder, rename)?,
+        Command::DeleteFolder { folder } => jottem::delete_folder(&folder)?,
+        Command::Tui => jottem::tui::run()?,
+        Command::Serve {
+            port,
+            bind,
+            token,
+            mut origins,
+        } => {
+            if origins.is_empty() {
+                origins = config::get_api_origins();
+            }
+
+            jottem::server::run(&bind, port, token.or_else(config::get_api_token), origins)?
+        }
+        Command::Watch => jottem::watch::run()?,
+        Command::Tree { args } => jottem::show_tree(&args)?,
+        Command::Completions { shell } => completions::print_script(shell)?,
+        Command::Man { out_dir } => jottem::generate_man_pages(&out_dir)?,
+        Command::Complete { index, words } => completions::print_candidates(index, &words)?,
+        Command::Import {
+            dir,
+            folder,
+            in_place,
+        } => jottem::import_notes(&dir, folder.as_deref(), in_place)?,
+        Command::Backup { file } => jottem::backup_notes(&file)?,
+        Command::Restore { file, conflict } => jottem::restore_notes(&file, conflict)?,
+        Command::ExportSite { out_dir } => jottem::export_site(&out_dir)?,
         Command::Export => jottem::export_index()?,
     };
 
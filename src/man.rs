@@ -0,0 +1,351 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Command, CommandFactory};
+use clap_mangen::Man;
+
+use crate::cli::Cli;
+
+/// Generates a roff man page for `jottem` and each of its subcommands.
+///
+/// * `out_dir` - directory to write the pages into (created if missing)
+///
+/// Pages are named after the full command, such as `jottem-tag-add.1`.
+/// Returns the paths of the generated pages.
+pub fn generate(out_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(out_dir).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to create man page directory: {}: {e}",
+            out_dir.to_string_lossy()
+        )
+    })?;
+
+    let mut cmd = Cli::command().disable_help_subcommand(true);
+    cmd.build();
+
+    let mut pages = Vec::new();
+    generate_page(cmd, out_dir, &mut pages)?;
+
+    Ok(pages)
+}
+
+fn generate_page(cmd: Command, out_dir: &Path, pages: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for subcommand in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+        generate_page(subcommand.clone(), out_dir, pages)?;
+    }
+
+    let name = cmd
+        .get_display_name()
+        .unwrap_or_else(|| cmd.get_name())
+        .to_string();
+
+    let has_options = cmd.get_arguments().any(|a| !a.is_hide_set());
+    let has_subcommands = cmd.get_subcommands().any(|s| !s.is_hide_set());
+    let has_version = cmd.get_version().is_some();
+
+    let man = Man::new(cmd);
+    let path = out_dir.join(man.get_filename());
+    let mut file = BufWriter::new(File::create(&path)?);
+
+    man.render_title(&mut file)?;
+    man.render_name_section(&mut file)?;
+    man.render_synopsis_section(&mut file)?;
+    man.render_description_section(&mut file)?;
+
+    if has_options {
+        man.render_options_section(&mut file)?;
+    }
+
+    if has_subcommands {
+        man.render_subcommands_section(&mut file)?;
+    }
+
+    render_examples(&name, &mut file)?;
+
+    if has_version {
+        man.render_version_section(&mut file)?;
+    }
+
+    file.flush()?;
+    pages.push(path);
+
+    Ok(())
+}
+
+/// Writes the EXAMPLES section for a command, if we have any examples for it.
+fn render_examples(name: &str, w: &mut dyn Write) -> std::io::Result<()> {
+    let examples = examples(name);
+
+    if examples.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w, ".SH EXAMPLES")?;
+
+    for (description, command) in examples {
+        writeln!(w, ".PP\n{}", escape(description))?;
+        writeln!(
+            w,
+            ".PP\n.RS 4\n{}\n.RE",
+            escape(command).replace('-', "\\-")
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Escapes text for use as a line of roff.
+fn escape(text: &str) -> String {
+    let text = text.replace('\\', "\\e");
+
+    if text.starts_with('.') || text.starts_with('\'') {
+        format!("\\&{text}")
+    } else {
+        text
+    }
+}
+
+/// Usage examples for each command, keyed by the name of its man page.
+fn examples(name: &str) -> &'static [(&'static str, &'static str)] {
+    match name {
+        "jottem" => &[
+            (
+                "Create a note and open it in $EDITOR:",
+                "jottem create my_note",
+            ),
+            ("List every note:", "jottem find"),
+            (
+                "Edit a note from a script, failing instead of prompting:",
+                "jottem edit todo --no-input || echo \"exit status $?\"",
+            ),
+        ],
+        "jottem-create" => &[
+            (
+                "Create a note inside a folder:",
+                "jottem create projects/alpha/todo",
+            ),
+            (
+                "Create a note with tags:",
+                "jottem create standup --tags work,daily",
+            ),
+            (
+                "Create a note from the output of a command:",
+                "ls -l | jottem create listing --stdin",
+            ),
+        ],
+        "jottem-append" => &[(
+            "Append piped input to a note:",
+            "echo \"call back later\" | jottem append todo",
+        )],
+        "jottem-jot" => &[(
+            "Jot a thought into the inbox note:",
+            "jottem jot remember to water the plants",
+        )],
+        "jottem-inbox" => &[("Open the inbox note in $EDITOR:", "jottem inbox")],
+        "jottem-inbox-process" => &[("Move each inbox entry into a note:", "jottem inbox process")],
+        "jottem-edit" => &[
+            ("Edit a note by title:", "jottem edit todo"),
+            ("Choose a note to edit with fuzzy finding:", "jottem edit"),
+            (
+                "Choose between notes similar to a partial title, such as meeting-notes:",
+                "jottem edit meet",
+            ),
+            (
+                "Create the note meet instead of offering similar notes:",
+                "jottem edit meet --exact",
+            ),
+        ],
+        "jottem-show" => &[
+            (
+                "Print a note without its frontmatter:",
+                "jottem show todo --no-frontmatter",
+            ),
+            (
+                "Print a single section of a note:",
+                "jottem show todo --section Today",
+            ),
+        ],
+        "jottem-find" => &[
+            ("Find notes by title:", "jottem find --path todo"),
+            (
+                "Find notes with any of the given tags:",
+                "jottem find --tags work,ideas",
+            ),
+            (
+                "Find notes tagged project or any tag below it, such as project/alpha:",
+                "jottem find --tags project",
+            ),
+            (
+                "Find the todo note in every project folder:",
+                "jottem find --glob 'projects/*/todo'",
+            ),
+            (
+                "Find daily notes from 2024:",
+                "jottem find --regex '^daily/2024-'",
+            ),
+            ("List every note under a folder:", "jottem find --in projects/"),
+            (
+                "Combine criteria to find work notes about the budget changed in the last week:",
+                "jottem find --in work --tags meeting --modified-after 7d --text budget",
+            ),
+            (
+                "Find the notes most relevant to some text, showing how relevant each is:",
+                "jottem find --text 'quarterly budget' --score",
+            ),
+            (
+                "List recently changed notes first:",
+                "jottem find --in projects/ --sort modified",
+            ),
+            (
+                "Open every note under a folder in $EDITOR:",
+                "$EDITOR $(jottem find --in projects/ --format json | jq -r '.[].absolute_path')",
+            ),
+        ],
+        "jottem-search" | "jottem-search-save" => &[(
+            "Save a search for standup notes from the last week:",
+            "jottem search save standup --tags standup --modified-after 7d",
+        )],
+        "jottem-search-run" => &[
+            ("Run a saved search:", "jottem search run standup"),
+            (
+                "Print only the paths of the notes a saved search finds:",
+                "jottem search run standup --format paths",
+            ),
+        ],
+        "jottem-search-list" => &[("List every saved search:", "jottem search list")],
+        "jottem-search-delete" => &[("Delete a saved search:", "jottem search delete standup")],
+        "jottem-watch" => &[(
+            "Keep the index up to date while pulling notes with git in another terminal:",
+            "jottem watch",
+        )],
+        "jottem-delete" => &[("Delete a note:", "jottem delete projects/alpha/todo")],
+        "jottem-tag" | "jottem-tag-add" => {
+            &[
+                ("Add tags to a note:", "jottem tag add todo work,urgent"),
+                (
+                    "Add a tag to notes chosen from a list:",
+                    "jottem tag add --tags urgent",
+                ),
+            ]
+        }
+        "jottem-tag-list" => &[("Show every tag as a tree:", "jottem tag list")],
+        "jottem-tag-rename" => &[(
+            "Rename a tag along with its descendants, such as project/alpha:",
+            "jottem tag rename project work/project",
+        )],
+        "jottem-tag-normalize" => &[
+            (
+                "Preview how existing tags would be normalized:",
+                "jottem tag normalize --dry-run",
+            ),
+            (
+                "Keep the case of tags when normalizing:",
+                "JOTTEM_TAG_CASE=preserve jottem tag normalize",
+            ),
+        ],
+        "jottem-tag-remove" => &[("Remove a tag from a note:", "jottem tag remove todo urgent")],
+        "jottem-alias" | "jottem-alias-add" => &[(
+            "Let `jottem edit standup` open the note daily/2024-01-15:",
+            "jottem alias add daily/2024-01-15 standup",
+        )],
+        "jottem-alias-remove" => &[(
+            "Remove an alias from a note:",
+            "jottem alias remove daily/2024-01-15 standup",
+        )],
+        "jottem-move" => &[
+            (
+                "Move a note into another folder:",
+                "jottem move todo archive/todo",
+            ),
+            (
+                "Rename a note in place:",
+                "jottem move projects/todo done --rename",
+            ),
+            (
+                "Move a note chosen from a list:",
+                "jottem move --to archive/todo",
+            ),
+        ],
+        "jottem-move-folder" => &[
+            (
+                "Move a folder of notes:",
+                "jottem move-folder projects/alpha archive/alpha",
+            ),
+            (
+                "Rename a folder in place:",
+                "jottem move-folder projects/alpha beta --rename",
+            ),
+        ],
+        "jottem-delete-folder" => &[("Delete a folder of notes:", "jottem delete-folder archive")],
+        "jottem-tree" => &[
+            ("Show every note as a tree:", "jottem tree"),
+            (
+                "Show two levels of a folder with tags:",
+                "jottem tree projects --depth 2 --tags",
+            ),
+        ],
+        "jottem-serve" => &[
+            ("Serve notes on http://127.0.0.1:4000:", "jottem serve"),
+            (
+                "Serve notes to the local network, requiring a token:",
+                "jottem serve --bind 0.0.0.0 --token hunter2",
+            ),
+            (
+                "List notes tagged work from another shell:",
+                "curl -H \"Authorization: Bearer hunter2\" \"http://localhost:4000/notes?tags=work\"",
+            ),
+        ],
+        "jottem-tui" => &[("Browse every note:", "jottem tui")],
+        "jottem-completions" => &[(
+            "Enable completions in the current bash session:",
+            "source <(jottem completions bash)",
+        )],
+        "jottem-man" => &[(
+            "Install the man pages for the current user:",
+            "jottem man ~/.local/share/man/man1",
+        )],
+        "jottem-import" => &[
+            (
+                "Copy an Obsidian vault into a folder:",
+                "jottem import ~/vault --folder vault",
+            ),
+            (
+                "Index markdown files already inside the notes directory:",
+                "jottem import ~/.local/share/jottem/old --in-place",
+            ),
+        ],
+        "jottem-backup" => &[("Back up every note:", "jottem backup notes.tar.gz")],
+        "jottem-restore" => &[(
+            "Restore a backup, keeping both copies of notes that already exist:",
+            "jottem restore notes.tar.gz --conflict rename",
+        )],
+        "jottem-export-site" => &[(
+            "Render every note to HTML and preview the site:",
+            "jottem export-site site && xdg-open site/index.html",
+        )],
+        "jottem-export" => &[("Save the index as JSON:", "jottem export > index.json")],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate_man_pages() {
+        let tmp = tempfile::tempdir().unwrap();
+        let pages = generate(tmp.path()).unwrap();
+
+        assert!(pages.contains(&tmp.path().join("jottem.1")));
+        assert!(pages.contains(&tmp.path().join("jottem-tag-add.1")));
+        assert!(!pages.contains(&tmp.path().join("jottem-complete.1")));
+
+        let page = std::fs::read_to_string(tmp.path().join("jottem-create.1")).unwrap();
+        assert!(page.contains(".SH EXAMPLES"));
+        assert!(page.contains("jottem create standup \\-\\-tags work,daily"));
+    }
+}
@@ -0,0 +1,455 @@
+use colored::Colorize;
+
+/// Splits a note into its YAML frontmatter and body.
+///
+/// Frontmatter must start on the first line with `---` and end with another
+/// `---` line. If the note has no (complete) frontmatter block, the whole
+/// content is returned as the body.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+
+    let mut offset = 0;
+
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let frontmatter = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(frontmatter), body);
+        }
+
+        offset += line.len();
+    }
+
+    (None, content)
+}
+
+/// Reads a list of values from YAML frontmatter.
+///
+/// * `keys` - keys to look for, in order of preference (such as `aliases` and `alias`)
+///
+/// The value can be a list or a single value. Returns an empty list if the
+/// frontmatter isn't valid YAML or has none of the keys.
+pub fn frontmatter_values(frontmatter: &str, keys: &[&str]) -> Vec<String> {
+    let Ok(yaml) = serde_yaml::from_str::<serde_yaml::Value>(frontmatter) else {
+        return Vec::new();
+    };
+
+    let Some(value) = keys.iter().find_map(|key| yaml.get(key)) else {
+        return Vec::new();
+    };
+
+    let scalar = |value: &serde_yaml::Value| match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+
+    match value {
+        serde_yaml::Value::Sequence(values) => values.iter().filter_map(scalar).collect(),
+        value => scalar(value).into_iter().collect(),
+    }
+}
+
+/// Sets a list of values in a note's frontmatter, keeping everything else as-is.
+///
+/// * `content` - the full note content
+/// * `key` - the frontmatter key to replace
+/// * `values` - the new values. If empty, the key is removed instead
+///
+/// The key is replaced where it was, along with any value on the following
+/// indented lines or list items, and added at the end if it's missing.
+/// A frontmatter block is added to the top of the note if it has none.
+/// Lines end with `\r\n` if the note already uses them.
+pub fn set_frontmatter_values(content: &str, key: &str, values: &[String]) -> String {
+    let eol = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut line = (!values.is_empty()).then(|| {
+        let values = values
+            .iter()
+            .map(|v| serde_json::Value::String(v.clone()).to_string())
+            .collect::<Vec<String>>();
+
+        format!("{key}: [{}]{eol}", values.join(", "))
+    });
+
+    let (frontmatter, body) = match split_frontmatter(content) {
+        (Some(frontmatter), body) => (frontmatter, body),
+        (None, body) => match line {
+            Some(line) => return format!("---{eol}{line}---{eol}{body}"),
+            None => return content.to_string(),
+        },
+    };
+
+    let mut out = format!("---{eol}");
+    let mut in_key = false;
+    // blank lines are only part of the key's value if it continues after them
+    let mut blank = String::new();
+
+    for current in frontmatter.split_inclusive('\n') {
+        if in_key {
+            if current.trim().is_empty() {
+                blank.push_str(current);
+                continue;
+            }
+
+            // a key's value continues on indented lines and list items
+            if current.starts_with([' ', '\t', '-']) {
+                blank.clear();
+                continue;
+            }
+
+            in_key = false;
+            out.push_str(&std::mem::take(&mut blank));
+        }
+
+        if current
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        {
+            in_key = true;
+            out.extend(line.take());
+            continue;
+        }
+
+        out.push_str(current);
+
+        if !current.ends_with('\n') {
+            out.push_str(eol);
+        }
+    }
+
+    out.push_str(&blank);
+    out.extend(line);
+
+    format!("{out}---{eol}{body}")
+}
+
+/// Parses a line as an ATX heading (such as `## Foo`), returning its level and text.
+pub fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let text = &line[level..];
+
+    if !text.is_empty() && !text.starts_with(' ') && !text.starts_with('\t') {
+        return None;
+    }
+
+    Some((level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+/// Returns true if the line opens or closes a fenced code block.
+pub fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// Extracts a single section of a note, starting at the heading with the given
+/// text and ending before the next heading of the same or a higher level.
+///
+/// * `body` - note content (without frontmatter)
+/// * `heading` - heading text to look for, compared case-insensitively
+///
+/// Headings inside fenced code blocks are ignored.
+pub fn extract_section(body: &str, heading: &str) -> Option<String> {
+    let heading = heading.trim().trim_start_matches('#').trim();
+    let mut section: Option<(usize, String)> = None;
+    let mut in_code = false;
+
+    for line in body.lines() {
+        if is_fence(line) {
+            in_code = !in_code;
+        }
+
+        let parsed = if in_code { None } else { parse_heading(line) };
+
+        match (&mut section, parsed) {
+            (Some((level, _)), Some((next, _))) if next <= *level => break,
+            (Some((_, text)), _) => {
+                text.push_str(line);
+                text.push('\n');
+            }
+            (None, Some((level, text))) if text.eq_ignore_ascii_case(heading) => {
+                section = Some((level, format!("{line}\n")));
+            }
+            (None, _) => {}
+        }
+    }
+
+    section.map(|(_, text)| text)
+}
+
+/// Extracts Obsidian-style `#tags` from a note body.
+///
+/// A tag starts with `#` at the beginning of a word and is made up of
+/// letters, numbers, `_`, `-` and `/`, with at least one non-numeric
+/// character (so `#1` isn't a tag). Headings, fenced code blocks and
+/// inline code are ignored. Tags are returned in order without duplicates.
+pub fn extract_hashtags(body: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for (_, tag) in find_hashtags(body) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+
+    tags
+}
+
+/// Renames `#tags` in a note body, following the same rules as [extract_hashtags].
+///
+/// * `rename` - returns the new name for a tag, or `None` to leave it as-is
+pub fn rename_hashtags(body: &str, rename: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut last = 0;
+
+    for (offset, tag) in find_hashtags(body) {
+        if let Some(new) = rename(tag) {
+            out.push_str(&body[last..offset]);
+            out.push_str(&new);
+            last = offset + tag.len();
+        }
+    }
+
+    out.push_str(&body[last..]);
+    out
+}
+
+/// Finds each `#tag` in a note body, along with the byte offset of the tag
+/// (after the `#`).
+fn find_hashtags(body: &str) -> Vec<(usize, &str)> {
+    let mut tags = Vec::new();
+    let mut in_code = false;
+    let mut offset = 0;
+
+    for line in body.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        if is_fence(line) {
+            in_code = !in_code;
+            continue;
+        }
+
+        if in_code || parse_heading(line.trim_start()).is_some() {
+            continue;
+        }
+
+        let mut in_inline_code = false;
+        let mut previous = ' ';
+
+        for (i, c) in line.char_indices() {
+            if c == '`' {
+                in_inline_code = !in_inline_code;
+            } else if c == '#' && !in_inline_code && previous.is_whitespace() {
+                let rest = &line[i + 1..];
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '/'))
+                    .unwrap_or(rest.len());
+                let tag = rest[..len].trim_end_matches('/');
+
+                if tag.chars().any(|c| !c.is_numeric()) {
+                    tags.push((start + i + 1, tag));
+                }
+            }
+
+            previous = c;
+        }
+    }
+
+    tags
+}
+
+/// Highlights markdown headings with terminal colors, leaving everything else as-is.
+pub fn colorize(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut in_code = false;
+
+    for line in body.lines() {
+        if is_fence(line) {
+            in_code = !in_code;
+            out.push_str(&line.dimmed().to_string());
+        } else if in_code {
+            out.push_str(&line.dimmed().to_string());
+        } else {
+            match parse_heading(line) {
+                Some((1, _)) => out.push_str(&line.bright_cyan().bold().underline().to_string()),
+                Some((2, _)) => out.push_str(&line.cyan().bold().to_string()),
+                Some(_) => out.push_str(&line.cyan().to_string()),
+                None => out.push_str(line),
+            }
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const NOTE: &str = "---
+tags: [foo]
+---
+# Title
+
+intro
+
+## First
+
+one
+```
+# not a heading
+```
+
+### Nested
+
+nested
+
+## Second
+
+two
+";
+
+    #[test]
+    fn split_note_frontmatter() {
+        let (frontmatter, body) = split_frontmatter(NOTE);
+
+        assert_eq!(frontmatter, Some("tags: [foo]\n"));
+        assert!(body.starts_with("# Title"));
+
+        let (frontmatter, body) = split_frontmatter("# Title\n---\n");
+
+        assert!(frontmatter.is_none());
+        assert_eq!(body, "# Title\n---\n");
+    }
+
+    #[test]
+    fn read_frontmatter_values() {
+        let frontmatter = "aliases:\n  - one\n  - Two Words\nalias: nope\ntag: single\n";
+
+        assert_eq!(
+            frontmatter_values(frontmatter, &["aliases", "alias"]),
+            vec!["one", "Two Words"]
+        );
+        assert_eq!(frontmatter_values(frontmatter, &["tag"]), vec!["single"]);
+        assert!(frontmatter_values(frontmatter, &["missing"]).is_empty());
+        assert!(frontmatter_values("not: [valid", &["not"]).is_empty());
+    }
+
+    #[test]
+    fn write_frontmatter_values() {
+        let values = vec!["a".to_string(), "b \"c\"".to_string()];
+
+        assert_eq!(
+            set_frontmatter_values("# Title\n", "aliases", &values),
+            "---\naliases: [\"a\", \"b \\\"c\\\"\"]\n---\n# Title\n"
+        );
+        assert_eq!(
+            set_frontmatter_values(NOTE, "aliases", &values[..1]),
+            NOTE.replacen("tags: [foo]\n", "tags: [foo]\naliases: [\"a\"]\n", 1)
+        );
+
+        let content = "---\naliases:\n  - old\n  - older\ntitle: x\n---\nbody\n";
+
+        assert_eq!(
+            set_frontmatter_values(content, "aliases", &[]),
+            "---\ntitle: x\n---\nbody\n"
+        );
+        assert_eq!(set_frontmatter_values("body", "aliases", &[]), "body");
+    }
+
+    #[test]
+    fn write_frontmatter_keeps_other_keys() {
+        let values = vec!["new".to_string()];
+
+        // a multi-line list is replaced where it was
+        let content =
+            "---\naliases:\n- old\n\n- older\n# comment\nother:\n  nested: 1\n---\nbody\n";
+        assert_eq!(
+            set_frontmatter_values(content, "aliases", &values),
+            "---\naliases: [\"new\"]\n# comment\nother:\n  nested: 1\n---\nbody\n"
+        );
+
+        // nested keys and block scalars under other keys are kept
+        let content = "---\nother:\n  aliases: nested\nnote: |\n  - not a list\naliases: [old]\n\ntitle: x\n---\n";
+        assert_eq!(
+            set_frontmatter_values(content, "aliases", &values),
+            "---\nother:\n  aliases: nested\nnote: |\n  - not a list\naliases: [\"new\"]\n\ntitle: x\n---\n"
+        );
+
+        let content = "---\r\ntitle: x\r\naliases:\r\n  - old\r\n---\r\nbody\r\n";
+        assert_eq!(
+            set_frontmatter_values(content, "aliases", &values),
+            "---\r\ntitle: x\r\naliases: [\"new\"]\r\n---\r\nbody\r\n"
+        );
+        assert_eq!(
+            set_frontmatter_values("body\r\n", "aliases", &values),
+            "---\r\naliases: [\"new\"]\r\n---\r\nbody\r\n"
+        );
+    }
+
+    #[test]
+    fn extract_note_section() {
+        let (_, body) = split_frontmatter(NOTE);
+
+        let section = extract_section(body, "first").unwrap();
+        assert!(section.starts_with("## First\n"));
+        assert!(section.contains("# not a heading"));
+        assert!(section.contains("### Nested"));
+        assert!(!section.contains("## Second"));
+
+        assert_eq!(
+            extract_section(body, "## Second").unwrap(),
+            "## Second\n\ntwo\n"
+        );
+        assert!(extract_section(body, "missing").is_none());
+    }
+
+    #[test]
+    fn extract_body_hashtags() {
+        let body = "# Heading #nope
+Some #work and #project/alpha, #1 or#not #work
+`#code` and
+```
+#fenced
+```
+#last-one.";
+
+        assert_eq!(
+            extract_hashtags(body),
+            vec!["work", "project/alpha", "last-one"]
+        );
+    }
+
+    #[test]
+    fn rename_body_hashtags() {
+        let body = "#project and #project/alpha but not #projects\n```\n#project\n```\n";
+        let renamed = rename_hashtags(body, |tag| {
+            (tag == "project" || tag.starts_with("project/"))
+                .then(|| tag.replacen("project", "work", 1))
+        });
+
+        assert_eq!(
+            renamed,
+            "#work and #work/alpha but not #projects\n```\n#project\n```\n"
+        );
+    }
+}
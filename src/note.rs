@@ -4,7 +4,7 @@ use std::hash::{Hash, Hasher};
 
 use serde::{Deserialize, Serialize};
 
-use crate::path::NotePath;
+use crate::{markdown, path::NotePath, tags};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Note {
@@ -16,6 +16,85 @@ pub struct Note {
     pub created: String,
     pub modified: String,
     pub tags: HashSet<String>,
+    /// Tags that come from `#hashtags` in the note body. These are also in
+    /// `tags`, but are removed again when the hashtag is removed.
+    /// Tags added manually are never in this set.
+    #[serde(default)]
+    pub inline_tags: HashSet<String>,
+    /// Alternative titles for looking up the note, kept in sync with the
+    /// `aliases` key in its frontmatter.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// A note as it's stored in the index.
+///
+/// Each change to [Note] adds a variant with the new layout, and older
+/// variants are converted when they're read.
+#[derive(Debug, Serialize, Deserialize)]
+enum Record {
+    V1(Note),
+}
+
+impl From<Record> for Note {
+    fn from(record: Record) -> Self {
+        match record {
+            Record::V1(note) => note,
+        }
+    }
+}
+
+/// Unversioned note records written before inline tags were tracked.
+#[derive(Debug, Deserialize)]
+struct LegacyNote {
+    absolute_path: String,
+    relative_path: String,
+    title: String,
+    created: String,
+    modified: String,
+    tags: HashSet<String>,
+}
+
+impl From<LegacyNote> for Note {
+    fn from(note: LegacyNote) -> Self {
+        Self {
+            absolute_path: note.absolute_path,
+            relative_path: note.relative_path,
+            title: note.title,
+            created: note.created,
+            modified: note.modified,
+            tags: note.tags,
+            inline_tags: HashSet::new(),
+            aliases: Vec::new(),
+        }
+    }
+}
+
+/// Unversioned note records written before aliases were added.
+#[derive(Debug, Deserialize)]
+struct LegacyNoteWithInlineTags {
+    absolute_path: String,
+    relative_path: String,
+    title: String,
+    created: String,
+    modified: String,
+    tags: HashSet<String>,
+    inline_tags: HashSet<String>,
+}
+
+impl From<LegacyNoteWithInlineTags> for Note {
+    fn from(note: LegacyNoteWithInlineTags) -> Self {
+        Self {
+            absolute_path: note.absolute_path,
+            relative_path: note.relative_path,
+            title: note.title,
+            created: note.created,
+            modified: note.modified,
+            tags: note.tags,
+            inline_tags: note.inline_tags,
+            aliases: Vec::new(),
+        }
+    }
 }
 
 impl Note {
@@ -26,6 +105,8 @@ impl Note {
         let created = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let modified = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let tags = HashSet::new();
+        let inline_tags = HashSet::new();
+        let aliases = Vec::new();
 
         let mut note = Self {
             absolute_path,
@@ -34,6 +115,8 @@ impl Note {
             created,
             modified,
             tags,
+            inline_tags,
+            aliases,
         };
 
         note.add_tags(note_tags);
@@ -48,19 +131,81 @@ impl Note {
         hasher.finish()
     }
 
+    /// Sets the modified time to now.
+    pub fn touch(&mut self) {
+        self.modified = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    }
+
+    /// Adds tags manually. Tags that were only inline are now kept even
+    /// if their hashtag is removed.
     pub fn add_tags(&mut self, tags: &[String]) {
         for tag in tags {
             self.tags.insert(tag.to_string());
+            self.inline_tags.remove(tag);
         }
     }
 
     pub fn remove_tags(&mut self, tags: &[String]) {
         self.tags.retain(|tag| !tags.contains(tag));
+        self.inline_tags.retain(|tag| !tags.contains(tag));
+    }
+
+    /// Updates everything that is read from the note content: inline tags
+    /// and aliases.
+    pub fn sync_content(&mut self, content: &str) {
+        self.sync_inline_tags(content);
+        self.sync_aliases(content);
+    }
+
+    /// Returns true if the note has the given title, or an alias for it.
+    pub fn has_title(&self, title: &str) -> bool {
+        self.title == title || self.aliases.iter().any(|alias| alias == title)
+    }
+
+    /// Updates the aliases from the `aliases` (or `alias`) frontmatter key.
+    pub fn sync_aliases(&mut self, content: &str) {
+        let (frontmatter, _) = markdown::split_frontmatter(content);
+        let mut seen = HashSet::new();
+
+        self.aliases = frontmatter
+            .map(|f| markdown::frontmatter_values(f, &["aliases", "alias"]))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|alias| validate_alias(alias).ok())
+            .filter(|alias| seen.insert(alias.clone()))
+            .collect();
+    }
+
+    /// Updates the inline tags from the `#hashtags` in the note content.
+    ///
+    /// * `content` - the full note content, including any frontmatter
+    ///
+    /// New hashtags are normalized (see [tags::normalize]) and added to
+    /// `tags`, and inline tags whose hashtag is gone are removed. Manually
+    /// added tags are left alone.
+    pub fn sync_inline_tags(&mut self, content: &str) {
+        let (_, body) = markdown::split_frontmatter(content);
+        let found: HashSet<String> = markdown::extract_hashtags(body)
+            .iter()
+            .filter_map(|tag| tags::normalize(tag).ok())
+            .collect();
+
+        for tag in self.inline_tags.difference(&found) {
+            self.tags.remove(tag);
+        }
+
+        self.inline_tags.retain(|tag| found.contains(tag));
+
+        for tag in found {
+            if self.tags.insert(tag.clone()) {
+                self.inline_tags.insert(tag);
+            }
+        }
     }
 
     pub fn serialize(&self) -> anyhow::Result<(u64, Vec<u8>)> {
         let id = self.id();
-        let serialized = bincode::serialize(&self)
+        let serialized = bincode::serialize(&Record::V1(self.clone()))
             .map_err(|e| anyhow::anyhow!("Failed to serialize note: {e}"))?
             .to_vec();
 
@@ -68,9 +213,134 @@ impl Note {
     }
 
     pub fn deserialize(record: &[u8]) -> anyhow::Result<Self> {
-        let note = bincode::deserialize(record)
+        let record = bincode::deserialize::<Record>(record)
             .map_err(|e| anyhow::anyhow!("Failed to deserialize note: {e}"))?;
 
+        Ok(record.into())
+    }
+
+    /// Reads a record written before records were versioned, which may have
+    /// any of the old layouts. Only used to migrate old indexes once.
+    pub(crate) fn deserialize_unversioned(record: &[u8]) -> anyhow::Result<Self> {
+        let note = bincode::deserialize(record)
+            .or_else(|_| bincode::deserialize::<LegacyNoteWithInlineTags>(record).map(Self::from))
+            .or_else(|_| bincode::deserialize::<LegacyNote>(record).map(Self::from))
+            .map_err(|e| anyhow::anyhow!("Failed to migrate note: {e}"))?;
+
         Ok(note)
     }
 }
+
+/// Trims an alias, returning an error if it can't be used as a title.
+pub fn validate_alias(alias: &str) -> anyhow::Result<String> {
+    let alias = alias.trim();
+
+    if alias.is_empty() {
+        return Err(anyhow::anyhow!("Error: Aliases can't be empty"));
+    }
+
+    if alias.contains('/') {
+        return Err(anyhow::anyhow!(
+            "Error: Invalid alias \"{alias}\": aliases can't contain \"/\""
+        ));
+    }
+
+    Ok(alias.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn note() -> Note {
+        Note::new(&NotePath::parse("foo/bar").unwrap(), &["manual".into()])
+    }
+
+    #[test]
+    fn sync_note_inline_tags() {
+        let mut note = note();
+
+        note.sync_inline_tags("---\ntags: [nope]\n---\n#todo #manual #idea\n");
+        assert_eq!(note.tags.len(), 3);
+        assert_eq!(note.inline_tags.len(), 2);
+
+        note.add_tags(&["idea".into()]);
+        note.sync_inline_tags("nothing left");
+
+        let mut tags = note.tags.iter().collect::<Vec<&String>>();
+        tags.sort();
+
+        assert_eq!(tags, vec!["idea", "manual"]);
+        assert!(note.inline_tags.is_empty());
+    }
+
+    #[test]
+    fn sync_note_aliases() {
+        let mut note = note();
+
+        note.sync_aliases("---\naliases: [Baz, \" qux \", bad/alias, qux, Baz]\n---\n");
+        assert_eq!(note.aliases, vec!["Baz", "qux"]);
+        assert!(note.has_title("bar") && note.has_title("qux"));
+
+        note.sync_aliases("no frontmatter");
+        assert!(note.aliases.is_empty());
+    }
+
+    #[test]
+    fn deserialize_legacy_note() {
+        #[derive(Serialize)]
+        struct Legacy<'a>(
+            &'a str,
+            &'a str,
+            &'a str,
+            &'a str,
+            &'a str,
+            &'a HashSet<String>,
+        );
+
+        let note = note();
+        let record = bincode::serialize(&Legacy(
+            &note.absolute_path,
+            &note.relative_path,
+            &note.title,
+            &note.created,
+            &note.modified,
+            &note.tags,
+        ))
+        .unwrap();
+
+        let legacy = Note::deserialize_unversioned(&record).unwrap();
+
+        assert_eq!(legacy.relative_path, "foo/bar");
+        assert!(legacy.tags.contains("manual"));
+        assert!(legacy.inline_tags.is_empty());
+
+        #[derive(Serialize)]
+        struct WithInlineTags<'a>(Legacy<'a>, &'a HashSet<String>);
+
+        let record = bincode::serialize(&WithInlineTags(
+            Legacy(
+                &note.absolute_path,
+                &note.relative_path,
+                &note.title,
+                &note.created,
+                &note.modified,
+                &note.tags,
+            ),
+            &note.tags,
+        ))
+        .unwrap();
+
+        let legacy = Note::deserialize_unversioned(&record).unwrap();
+
+        assert!(legacy.inline_tags.contains("manual"));
+        assert!(legacy.aliases.is_empty());
+
+        let record = bincode::serialize(&note).unwrap();
+        assert!(Note::deserialize_unversioned(&record).is_ok());
+        assert!(Note::deserialize(&record).is_err());
+
+        let (_, record) = note.serialize().unwrap();
+        assert!(Note::deserialize(&record).is_ok());
+    }
+}
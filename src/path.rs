@@ -43,6 +43,21 @@ impl NotePath {
         Ok(Self { title, parent })
     }
 
+    /// Like [NotePath::parse], but also rejects paths that could escape the
+    /// root notes directory (empty, `.` or `..` components).
+    ///
+    /// Used for paths that don't come from the user directly, such as
+    /// API requests and backup archives.
+    pub fn parse_contained(input: &str) -> anyhow::Result<Self> {
+        let input = input.trim_matches('/');
+
+        if !is_contained(input) {
+            return Err(anyhow::anyhow!("Error: Invalid note path: {input}"));
+        }
+
+        Self::parse(input)
+    }
+
     /// Creates a new [NotePath] from an existing [Note] struct
     pub fn from_note(note: &Note) -> anyhow::Result<Self> {
         Self::parse(&note.relative_path)
@@ -88,6 +103,15 @@ impl NotePath {
     }
 }
 
+/// Returns true if a relative path (without leading or trailing slashes)
+/// stays inside the root notes directory: it isn't empty and has no empty,
+/// `.` or `..` components.
+pub fn is_contained(path: &str) -> bool {
+    !path
+        .split('/')
+        .any(|s| s.is_empty() || s == "." || s == "..")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -118,4 +142,14 @@ mod test {
         assert!(path.parent.is_some());
         assert_eq!(path.parent, Some("parent".into()));
     }
+
+    #[test]
+    fn parse_contained_path() {
+        assert!(NotePath::parse_contained("foo/bar").is_ok());
+        assert!(NotePath::parse_contained("/foo/").is_ok());
+        assert!(NotePath::parse_contained("../foo").is_err());
+        assert!(NotePath::parse_contained("foo/./bar").is_err());
+        assert!(NotePath::parse_contained("foo//bar").is_err());
+        assert!(NotePath::parse_contained("").is_err());
+    }
 }
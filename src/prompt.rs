@@ -1,7 +1,57 @@
-use dialoguer::{theme::ColorfulTheme, Confirm, FuzzySelect, Select};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, Confirm, FuzzySelect, Input, MultiSelect, Select};
+
+use crate::config;
+
+/// Exit status when no notes match the user input and prompting is disabled.
+pub const EXIT_NO_MATCHES: i32 = 3;
+/// Exit status when several notes match the user input and prompting is disabled.
+pub const EXIT_MULTIPLE_MATCHES: i32 = 4;
+/// Exit status when an action needs user input but prompting is disabled.
+pub const EXIT_INPUT_REQUIRED: i32 = 5;
+
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables prompting the user.
+///
+/// This is disabled by `--no-input`, or when stdin isn't a terminal.
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.store(interactive, Ordering::Relaxed);
+}
+
+/// Returns true if the user may be prompted for input.
+pub fn is_interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+/// Answers yes to every confirmation instead of prompting, as with `--yes`.
+pub fn set_assume_yes(yes: bool) {
+    ASSUME_YES.store(yes, Ordering::Relaxed);
+}
+
+/// Exits with [EXIT_INPUT_REQUIRED] if prompting is disabled.
+fn require_input() {
+    if !is_interactive() {
+        eprintln!(
+            "{}",
+            "Input is required, but prompts are disabled (--no-input or stdin is not a terminal)"
+                .bright_red()
+        );
+        std::process::exit(EXIT_INPUT_REQUIRED);
+    }
+}
 
 /// Prompts the user to ask if they would like to create a new note.
 pub fn no_matches() -> anyhow::Result<bool> {
+    require_input();
+
     let res = Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt("No note found with that name. Would you like to create it now?")
         .default(true)
@@ -14,8 +64,47 @@ pub fn no_matches() -> anyhow::Result<bool> {
     Ok(res.unwrap())
 }
 
+/// Prompts the user to confirm an action that affects several notes at once.
+///
+/// * `action` - short description of the action, such as `Delete`
+/// * `count` - number of notes that will be affected
+///
+/// Always returns true if `--yes` was given, without prompting.
+pub fn confirm_bulk(action: &str, count: usize) -> anyhow::Result<bool> {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        return Ok(true);
+    }
+
+    require_input();
+
+    let res = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("{action} {count} notes?"))
+        .default(false)
+        .interact_opt()?;
+
+    Ok(res.unwrap_or(false))
+}
+
+/// Asks the user to choose one of the notes similar to what they typed.
+pub fn similar_matches(matches: &[&str]) -> anyhow::Result<usize> {
+    require_input();
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("No exact match found. Did you mean")
+        .default(0)
+        .items(matches)
+        .interact_opt()?;
+
+    match selection {
+        Some(selection) => Ok(selection),
+        None => std::process::exit(0),
+    }
+}
+
 /// Prompts the user to choose a single note from multiple matching notes.
 pub fn multiple_matches(matches: &[&str]) -> anyhow::Result<usize> {
+    require_input();
+
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("Multiple notes found. Please choose one")
         .default(0)
@@ -31,6 +120,8 @@ pub fn multiple_matches(matches: &[&str]) -> anyhow::Result<usize> {
 
 /// Prompts the user to choose a note with fuzzy finding
 pub fn select_fuzzy(notes: &[&str]) -> anyhow::Result<usize> {
+    require_input();
+
     let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
         .items(notes)
         .interact_opt()?;
@@ -42,3 +133,150 @@ pub fn select_fuzzy(notes: &[&str]) -> anyhow::Result<usize> {
 
     Ok(selection.unwrap())
 }
+
+/// Prompts the user to choose any number of notes.
+pub fn select_multiple(notes: &[&str]) -> anyhow::Result<Vec<usize>> {
+    require_input();
+
+    let selection = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Choose notes with <space> and confirm with <enter>")
+        .items(notes)
+        .interact_opt()?;
+
+    let Some(selection) = selection else {
+        // user cancelled
+        std::process::exit(0);
+    };
+
+    Ok(selection)
+}
+
+/// Returns true if notes should be chosen with fzf. See [config::get_picker].
+pub fn use_fzf() -> bool {
+    match config::get_picker().as_str() {
+        "fzf" => true,
+        "builtin" => false,
+        _ => std::env::var_os("PATH").is_some_and(|paths| {
+            std::env::split_paths(&paths).any(|dir| dir.join("fzf").is_file())
+        }),
+    }
+}
+
+/// Prompts the user to choose notes with fzf, showing the first lines of
+/// the highlighted note in a preview window.
+///
+/// * `options` - the text displayed for each note
+/// * `files` - absolute path of each note, used for the preview
+/// * `multi` - allow choosing more than one note
+/// * `header` - optional text displayed above the options
+pub fn select_fzf(
+    options: &[&str],
+    files: &[&str],
+    multi: bool,
+    header: Option<&str>,
+) -> anyhow::Result<Vec<usize>> {
+    require_input();
+
+    let mut command = Command::new("fzf");
+
+    // each line is `index<TAB>file<TAB>option`, but only the option is displayed
+    command
+        .args(["--delimiter", "\t", "--with-nth", "3..", "--ansi"])
+        .args([
+            "--preview",
+            "head -n 100 {2}",
+            "--preview-window",
+            "right,60%",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped());
+
+    if multi {
+        command.arg("--multi");
+    }
+
+    if let Some(header) = header {
+        command.args(["--header", header]);
+    }
+
+    let mut fzf = command
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to run fzf: {e}"))?;
+
+    let input = options
+        .iter()
+        .zip(files)
+        .enumerate()
+        .map(|(i, (option, file))| format!("{i}\t{file}\t{option}\n"))
+        .collect::<String>();
+
+    if let Some(mut stdin) = fzf.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+
+    let output = fzf.wait_with_output()?;
+
+    if !output.status.success() {
+        // user cancelled or nothing matched
+        std::process::exit(0);
+    }
+
+    let selection = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split('\t').next()?.parse().ok())
+        .collect::<Vec<usize>>();
+
+    Ok(selection)
+}
+
+/// Actions available for each entry when processing the inbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboxAction {
+    NewNote,
+    ExistingNote,
+    Keep,
+    Delete,
+    Stop,
+}
+
+/// Prompts the user to choose what to do with an inbox entry.
+pub fn inbox_action() -> anyhow::Result<InboxAction> {
+    require_input();
+
+    let actions = [
+        (InboxAction::NewNote, "Move to a new note"),
+        (InboxAction::ExistingNote, "Move to an existing note"),
+        (InboxAction::Keep, "Keep in the inbox"),
+        (InboxAction::Delete, "Delete"),
+        (InboxAction::Stop, "Stop processing"),
+    ];
+
+    let items = actions
+        .iter()
+        .map(|(_, label)| *label)
+        .collect::<Vec<&str>>();
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("What should be done with this entry?")
+        .default(0)
+        .items(&items)
+        .interact_opt()?;
+
+    let Some(selection) = selection else {
+        // user cancelled
+        std::process::exit(0);
+    };
+
+    Ok(actions[selection].0)
+}
+
+/// Prompts the user to enter a line of text.
+pub fn input(prompt: &str) -> anyhow::Result<String> {
+    require_input();
+
+    let input = Input::<String>::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .interact_text()?;
+
+    Ok(input)
+}
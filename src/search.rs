@@ -0,0 +1,506 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use clap::ValueEnum;
+use glob::Pattern;
+use regex::Regex;
+
+use crate::{
+    cli::{SearchArgs, SortOrder},
+    index::{Index, GLOB_OPTIONS},
+    markdown,
+    note::Note,
+    path::NotePath,
+    tags,
+};
+
+/// The format of `Note.created` and `Note.modified`, which sorts by date.
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// BM25 term frequency saturation.
+const K1: f64 = 1.2;
+/// BM25 document length normalization.
+const B: f64 = 0.75;
+/// How much a search term in the title (or an alias) counts, relative to
+/// the same term in the body.
+const TITLE_WEIGHT: f64 = 2.0;
+/// How much a search term in a tag counts, relative to the same term in
+/// the body.
+const TAG_WEIGHT: f64 = 1.0;
+
+/// A parsed [SearchArgs] query. A note matches if it passes every
+/// criterion that was given, so no criteria at all matches every note.
+#[derive(Debug, Default)]
+pub struct Query {
+    path: Option<NotePath>,
+    folder: Option<String>,
+    glob: Option<(String, Pattern)>,
+    regex: Option<Regex>,
+    tags: Vec<String>,
+    created: DateRange,
+    modified: DateRange,
+    terms: Vec<String>,
+    sort: Option<SortOrder>,
+}
+
+/// A note found by a [Query].
+#[derive(Debug)]
+pub struct Hit {
+    pub note: Note,
+    /// Relevance to the search text (see [Query::run]), if there was any.
+    pub score: Option<f64>,
+}
+
+/// Inclusive lower and exclusive upper bounds, formatted like [DATE_FORMAT].
+#[derive(Debug, Default)]
+struct DateRange {
+    after: Option<String>,
+    before: Option<String>,
+}
+
+impl DateRange {
+    fn parse(after: Option<&str>, before: Option<&str>) -> anyhow::Result<Self> {
+        Ok(Self {
+            after: after.map(parse_date).transpose()?,
+            before: before.map(parse_date).transpose()?,
+        })
+    }
+
+    fn contains(&self, date: &str) -> bool {
+        self.after
+            .as_ref()
+            .is_none_or(|after| date >= after.as_str())
+            && self
+                .before
+                .as_ref()
+                .is_none_or(|before| date < before.as_str())
+    }
+}
+
+impl Query {
+    /// Validates the search parameters, returning an error for invalid
+    /// paths, patterns, tags or dates.
+    pub fn parse(args: &SearchArgs) -> anyhow::Result<Self> {
+        let glob = match &args.glob {
+            Some(pattern) => Some((
+                pattern.to_string(),
+                Pattern::new(pattern).map_err(|e| {
+                    anyhow::anyhow!("Error: Invalid glob pattern \"{pattern}\": {e}")
+                })?,
+            )),
+            None => None,
+        };
+
+        let regex = match &args.regex {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                anyhow::anyhow!("Error: Invalid regular expression \"{pattern}\": {e}")
+            })?),
+            None => None,
+        };
+
+        Ok(Self {
+            path: args.path.as_deref().map(NotePath::parse).transpose()?,
+            folder: args
+                .folder
+                .as_ref()
+                .map(|folder| format!("{}/", folder.trim_matches('/'))),
+            glob,
+            regex,
+            tags: tags::normalize_query(&args.tags),
+            created: DateRange::parse(
+                args.created_after.as_deref(),
+                args.created_before.as_deref(),
+            )?,
+            modified: DateRange::parse(
+                args.modified_after.as_deref(),
+                args.modified_before.as_deref(),
+            )?,
+            terms: args
+                .text
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .collect(),
+            sort: args.sort,
+        })
+    }
+
+    /// Finds every note matching the query.
+    ///
+    /// The path, folder or glob pattern narrows down the notes read from
+    /// the index, and the remaining criteria filter those. The note files
+    /// are only read if there is text to search for.
+    ///
+    /// With search text, each note is scored with BM25 over its body, plus
+    /// weighted matches in its title, aliases and tags. Every note passing
+    /// the other criteria counts towards the term statistics, not just the
+    /// notes containing the text. Results are sorted by score unless
+    /// another [SortOrder] was chosen, and by path otherwise.
+    pub fn run(&self, index: &Index) -> anyhow::Result<Vec<Hit>> {
+        let notes = if let Some(path) = &self.path {
+            index.find(path)?
+        } else if let Some(folder) = &self.folder {
+            index.find_by_folder(folder)?
+        } else if let Some((pattern, _)) = &self.glob {
+            index.find_by_glob(pattern)?
+        } else {
+            index.get_all()?
+        };
+
+        let notes = notes.into_iter().filter(|note| self.matches_metadata(note));
+
+        let mut hits = if self.terms.is_empty() {
+            notes.map(|note| Hit { note, score: None }).collect()
+        } else {
+            self.score(notes.collect())
+        };
+
+        let sort = match self.sort {
+            Some(sort) => sort,
+            None if self.terms.is_empty() => SortOrder::Path,
+            None => SortOrder::Score,
+        };
+
+        hits.sort_by(|a, b| {
+            let by_path = a.note.relative_path.cmp(&b.note.relative_path);
+
+            match sort {
+                SortOrder::Score => b
+                    .score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(Ordering::Equal)
+                    .then(by_path),
+                SortOrder::Path => by_path,
+                SortOrder::Modified => b.note.modified.cmp(&a.note.modified).then(by_path),
+                SortOrder::Created => b.note.created.cmp(&a.note.created).then(by_path),
+            }
+        });
+
+        Ok(hits)
+    }
+
+    /// Returns true if the note passes every criterion that doesn't need
+    /// its content.
+    fn matches_metadata(&self, note: &Note) -> bool {
+        let path = note.relative_path.as_str();
+
+        self.folder
+            .as_ref()
+            .is_none_or(|folder| path.starts_with(folder.as_str()))
+            && self
+                .glob
+                .as_ref()
+                .is_none_or(|(_, glob)| glob.matches_with(path, GLOB_OPTIONS))
+            && self.regex.as_ref().is_none_or(|regex| regex.is_match(path))
+            && (self.tags.is_empty()
+                || note
+                    .tags
+                    .iter()
+                    .any(|tag| self.tags.iter().any(|query| tags::matches(tag, query))))
+            && self.created.contains(&note.created)
+            && self.modified.contains(&note.modified)
+    }
+
+    fn contains_terms(&self, content: &str) -> bool {
+        self.terms
+            .iter()
+            .all(|term| content.contains(term.as_str()))
+    }
+
+    /// Scores the notes containing every search term. See [Query::run]
+    fn score(&self, notes: Vec<Note>) -> Vec<Hit> {
+        let terms = self
+            .terms
+            .iter()
+            .flat_map(|term| tokenize(term))
+            .collect::<Vec<String>>();
+
+        let docs = notes
+            .into_iter()
+            .filter_map(|note| {
+                let content = std::fs::read_to_string(&note.absolute_path).ok()?;
+                let content = content.to_lowercase();
+                let (_, body) = markdown::split_frontmatter(&content);
+                let tokens = tokenize(body);
+                let matched = self.contains_terms(&content);
+
+                Some((note, tokens, matched))
+            })
+            .collect::<Vec<_>>();
+
+        let count = docs.len() as f64;
+        let avg_len = docs
+            .iter()
+            .map(|(_, tokens, _)| tokens.len() as f64)
+            .sum::<f64>()
+            / count.max(1.0);
+
+        let idf = terms
+            .iter()
+            .map(|term| {
+                let with_term = docs
+                    .iter()
+                    .filter(|(_, tokens, _)| frequency(tokens, term) > 0)
+                    .count() as f64;
+
+                let idf = (1.0 + (count - with_term + 0.5) / (with_term + 0.5)).ln();
+                (term.as_str(), idf)
+            })
+            .collect::<HashMap<&str, f64>>();
+
+        docs.into_iter()
+            .filter(|(_, _, matched)| *matched)
+            .map(|(note, tokens, _)| {
+                let len = tokens.len() as f64;
+                let names = std::iter::once(&note.title)
+                    .chain(&note.aliases)
+                    .flat_map(|name| tokenize(name))
+                    .collect::<Vec<String>>();
+                let tags = note
+                    .tags
+                    .iter()
+                    .flat_map(|tag| tokenize(tag))
+                    .collect::<Vec<String>>();
+
+                let score = terms
+                    .iter()
+                    .map(|term| {
+                        let tf = frequency(&tokens, term) as f64;
+                        let body =
+                            tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / avg_len.max(1.0)));
+                        let title = if frequency(&names, term) > 0 {
+                            TITLE_WEIGHT
+                        } else {
+                            0.0
+                        };
+                        let tag = if frequency(&tags, term) > 0 {
+                            TAG_WEIGHT
+                        } else {
+                            0.0
+                        };
+
+                        idf[term.as_str()] * (body + title + tag)
+                    })
+                    .sum();
+
+                Hit {
+                    note,
+                    score: Some(score),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Splits text into lowercase words for scoring.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Counts the words containing a term, so `budget` also counts `budgets`
+/// just like it matches them.
+fn frequency(words: &[String], term: &str) -> usize {
+    words.iter().filter(|word| word.contains(term)).count()
+}
+
+/// Trims the name of a saved search, returning an error if it's empty.
+pub fn validate_name(name: &str) -> anyhow::Result<String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Error: Search names can't be empty"));
+    }
+
+    Ok(name.to_string())
+}
+
+/// Formats search parameters as the `jottem find` options that produce
+/// them, such as `--tags standup --modified-after 7d`.
+pub fn describe(args: &SearchArgs) -> String {
+    let options = [
+        ("--path", args.path.clone()),
+        (
+            "--tags",
+            (!args.tags.is_empty()).then(|| args.tags.join(",")),
+        ),
+        ("--glob", args.glob.clone()),
+        ("--regex", args.regex.clone()),
+        ("--in", args.folder.clone()),
+        ("--created-after", args.created_after.clone()),
+        ("--created-before", args.created_before.clone()),
+        ("--modified-after", args.modified_after.clone()),
+        ("--modified-before", args.modified_before.clone()),
+        ("--text", args.text.clone()),
+        (
+            "--sort",
+            args.sort
+                .and_then(|sort| sort.to_possible_value())
+                .map(|sort| sort.get_name().to_string()),
+        ),
+    ];
+
+    let described = options
+        .into_iter()
+        .filter_map(|(option, value)| Some(format!("{option} {}", quote(&value?))))
+        .collect::<Vec<String>>();
+
+    if described.is_empty() {
+        "--all".to_string()
+    } else {
+        described.join(" ")
+    }
+}
+
+/// Quotes a value for the shell if it contains anything but plain characters.
+fn quote(value: &str) -> String {
+    let plain = |c: char| c.is_alphanumeric() || "-_/.,:".contains(c);
+
+    if !value.is_empty() && value.chars().all(plain) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Parses a date given on the command line into [DATE_FORMAT].
+///
+/// Accepts `YYYY-MM-DD`, `YYYY-MM-DD HH:MM[:SS]`, or a time ago such as
+/// `12h`, `7d` or `2w` (hours, days or weeks).
+pub fn parse_date(input: &str) -> anyhow::Result<String> {
+    let input = input.trim();
+    let invalid = || {
+        anyhow::anyhow!(
+            "Error: Invalid date \"{input}\": use YYYY-MM-DD, YYYY-MM-DD HH:MM or a time ago such as 7d"
+        )
+    };
+
+    if let Some(unit) = input.chars().last().filter(char::is_ascii_alphabetic) {
+        // unsigned, so a negative time ago can't turn into a future date
+        let amount: u32 = input[..input.len() - 1].parse().map_err(|_| invalid())?;
+        let amount = i64::from(amount);
+        let ago = match unit {
+            'h' => chrono::Duration::try_hours(amount),
+            'd' => chrono::Duration::try_days(amount),
+            'w' => chrono::Duration::try_weeks(amount),
+            _ => None,
+        }
+        .ok_or_else(invalid)?;
+
+        let date = chrono::Local::now()
+            .checked_sub_signed(ago)
+            .ok_or_else(invalid)?;
+
+        return Ok(date.format(DATE_FORMAT).to_string());
+    }
+
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(date.format("%Y-%m-%d 00:00:00").to_string());
+    }
+
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(input, format).ok())
+        .map(|date| date.format(DATE_FORMAT).to_string())
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod test {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    fn parse_search_dates() {
+        assert_eq!(parse_date("2024-01-15").unwrap(), "2024-01-15 00:00:00");
+        assert_eq!(
+            parse_date("2024-01-15 09:30").unwrap(),
+            "2024-01-15 09:30:00"
+        );
+
+        let week_ago = parse_date("7d").unwrap();
+        assert_eq!(parse_date("1w").unwrap()[..10], week_ago[..10]);
+        assert!(week_ago < parse_date("12h").unwrap());
+
+        for invalid in ["yesterday", "7y", "d", "-7d", "2024-13-01"] {
+            assert!(parse_date(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn describe_search() {
+        let args = SearchArgs {
+            tags: vec!["standup".into(), "work".into()],
+            modified_after: Some("7d".into()),
+            text: Some("budget review".into()),
+            ..SearchArgs::default()
+        };
+
+        assert_eq!(
+            describe(&args),
+            "--tags standup,work --modified-after 7d --text 'budget review'"
+        );
+        assert_eq!(describe(&SearchArgs::default()), "--all");
+        assert_eq!(quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    #[serial]
+    fn match_combined_criteria() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::env::set_var("JOTTEM_ROOT", tmp.path());
+        std::env::set_var("JOTTEM_DB_PATH", tmp.path());
+
+        let path = NotePath::parse("work/alpha/todo").unwrap();
+        let mut note = crate::utils::create_note(&path, &["project/alpha".into()]).unwrap();
+        note.modified = "2024-01-15 09:30:00".into();
+        std::fs::write(&note.absolute_path, "Pay the Invoice").unwrap();
+
+        let other = NotePath::parse("home/todo").unwrap();
+        crate::utils::create_note(&other, &["project".into()]).unwrap();
+
+        let index = Index::open().unwrap();
+        index.insert(&note).unwrap();
+
+        let run = |args: &SearchArgs| {
+            Query::parse(args)
+                .unwrap()
+                .run(&index)
+                .unwrap()
+                .into_iter()
+                .map(|hit| hit.note.relative_path)
+                .collect::<Vec<String>>()
+        };
+
+        let args = SearchArgs {
+            folder: Some("work".into()),
+            tags: vec!["project".into()],
+            modified_after: Some("2024-01-01".into()),
+            text: Some("invoice".into()),
+            ..SearchArgs::default()
+        };
+        assert_eq!(run(&args), vec!["work/alpha/todo"]);
+
+        let args = SearchArgs {
+            modified_before: Some("2024-01-15".into()),
+            ..args
+        };
+        assert!(run(&args).is_empty());
+
+        let args = SearchArgs {
+            glob: Some("work/*".into()),
+            ..SearchArgs::default()
+        };
+        assert!(run(&args).is_empty());
+
+        let args = SearchArgs {
+            tags: vec!["project".into()],
+            text: Some("receipt".into()),
+            ..SearchArgs::default()
+        };
+        assert!(run(&args).is_empty());
+    }
+}
@@ -0,0 +1,769 @@
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+};
+
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    cli::{SearchArgs, SortOrder},
+    file,
+    index::Index,
+    note::Note,
+    path::NotePath,
+    search::Query,
+    tags, utils,
+};
+
+/// Request bodies larger than this are rejected.
+const MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+
+/// An error returned to the client as `{"error": "..."}` with an HTTP status.
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(400, message)
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self::new(404, message)
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(500, e.to_string())
+    }
+}
+
+type ApiResult = Result<(u16, Option<String>), ApiError>;
+
+/// A note along with the contents of its file.
+#[derive(Debug, Serialize)]
+struct NoteContent {
+    #[serde(flatten)]
+    note: Note,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateBody {
+    path: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateBody {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsBody {
+    tags: Vec<String>,
+}
+
+/// Who may call the API.
+#[derive(Debug, Default)]
+struct Access {
+    /// If set, every request must send `Authorization: Bearer <token>`
+    token: Option<String>,
+    /// `Host` headers that are accepted, such as `127.0.0.1:4000`, which
+    /// stops other sites from reaching the server through DNS rebinding.
+    /// Any host is accepted if this is empty.
+    hosts: Vec<String>,
+    /// Origins of the web pages allowed to call the API, besides pages
+    /// served from the API's own host
+    origins: Vec<String>,
+}
+
+impl Access {
+    /// * `address` - the address the server listens on
+    fn new(address: SocketAddr, token: Option<String>, origins: Vec<String>) -> Self {
+        let port = address.port();
+
+        let hosts = if address.ip().is_unspecified() {
+            // listening on every interface, so there's no single host name
+            Vec::new()
+        } else if address.ip().is_loopback() {
+            vec![
+                address.to_string(),
+                format!("localhost:{port}"),
+                format!("127.0.0.1:{port}"),
+                format!("[::1]:{port}"),
+            ]
+        } else {
+            vec![address.to_string()]
+        };
+
+        Self {
+            token,
+            hosts,
+            origins: origins
+                .into_iter()
+                .map(|o| o.trim_end_matches('/').to_string())
+                .collect(),
+        }
+    }
+
+    fn host_allowed(&self, host: Option<&str>) -> bool {
+        self.hosts.is_empty()
+            || host.is_some_and(|host| self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host)))
+    }
+
+    fn origin_allowed(&self, origin: &str, host: Option<&str>) -> bool {
+        self.origins.iter().any(|o| o == "*" || o == origin)
+            || host.is_some_and(|host| origin.eq_ignore_ascii_case(&format!("http://{host}")))
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+
+        let expected = format!("Bearer {token}");
+
+        header(request, "Authorization") == Some(expected.as_str())
+    }
+}
+
+/// Serves the notes over a small REST API until the process is stopped.
+///
+/// * `bind` - address to listen on, `127.0.0.1` unless the user asks otherwise
+/// * `port` - port to listen on
+/// * `token` - if set, every request must send `Authorization: Bearer <token>`
+/// * `origins` - origins of the web pages allowed to call the API, or `*`
+///   for any page. Pages served from the API's own host are always allowed.
+///
+/// Requests must use the host the server listens on (unless it listens on
+/// every interface), and bodies must be sent as `application/json`, so
+/// other web pages can't reach the API without a CORS preflight.
+///
+/// Endpoints:
+/// * `GET /notes` - list or search notes, with the options of `jottem find`
+///   as parameters: `?text=`, `?tags=a,b`, `?path=`, `?in=`, `?glob=`,
+///   `?regex=`, `?created_after=` and so on, and `?sort=`
+/// * `POST /notes` - create a note from `{"path", "tags", "content"}`
+/// * `GET /notes/<path>` - get a note along with its content
+/// * `PUT /notes/<path>` - replace the content of a note with `{"content"}`
+/// * `DELETE /notes/<path>` - delete a note
+/// * `GET /tags` - list every tag with the number of notes using it
+/// * `POST /tags/<path>` / `DELETE /tags/<path>` - add or remove `{"tags"}`
+pub fn run(
+    bind: &str,
+    port: u16,
+    token: Option<String>,
+    origins: Vec<String>,
+) -> anyhow::Result<()> {
+    let address = socket_address(bind, port)?;
+    let server = Server::http(address)
+        .map_err(|e| anyhow::anyhow!("Failed to start server on {address}: {e}"))?;
+
+    let is_local = address.ip().is_loopback();
+
+    if !is_local && token.is_none() {
+        eprintln!(
+            "{}",
+            "Warning: serving notes on a public address without a token".bright_yellow()
+        );
+    }
+
+    let access = Access::new(address, token, origins);
+
+    println!("Serving notes on http://{address}");
+
+    for mut request in server.incoming_requests() {
+        let (result, headers) = process(&mut request, &access);
+        let (status, body) = reply(result);
+
+        println!("{} {} {status}", request.method(), request.url());
+
+        let mut response = match body {
+            Some(body) => Response::from_string(body)
+                .with_status_code(status)
+                .with_header(json_header()),
+            None => Response::from_string("").with_status_code(status),
+        };
+
+        for header in headers {
+            response.add_header(header);
+        }
+
+        if let Err(e) = request.respond(response) {
+            eprintln!("{}", format!("Failed to send response: {e}").bright_red());
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves the address to listen on. IPv6 addresses such as `::1` are
+/// accepted without brackets, as well as host names such as `localhost`.
+fn socket_address(bind: &str, port: u16) -> anyhow::Result<SocketAddr> {
+    let bind = bind.trim_start_matches('[').trim_end_matches(']');
+
+    if let Ok(ip) = bind.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, port));
+    }
+
+    (bind, port)
+        .to_socket_addrs()
+        .map_err(|e| anyhow::anyhow!("Error: Invalid address to listen on: {bind}: {e}"))?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Error: Invalid address to listen on: {bind}"))
+}
+
+/// Checks where a request comes from, answers CORS preflights and routes
+/// everything else to [handle].
+///
+/// Returns the result along with the CORS headers to send.
+fn process(request: &mut Request, access: &Access) -> (ApiResult, Vec<Header>) {
+    let host = header(request, "Host").map(String::from);
+    let origin = header(request, "Origin").map(String::from);
+
+    if !access.host_allowed(host.as_deref()) {
+        return (Err(ApiError::new(403, "Host not allowed")), Vec::new());
+    }
+
+    let mut headers = Vec::new();
+
+    if let Some(origin) = origin {
+        if !access.origin_allowed(&origin, host.as_deref()) {
+            return (Err(ApiError::new(403, "Origin not allowed")), Vec::new());
+        }
+
+        headers.push(new_header("Access-Control-Allow-Origin", &origin));
+        headers.push(new_header("Vary", "Origin"));
+    }
+
+    // browsers never send credentials with a preflight
+    if request.method() == &Method::Options {
+        headers.push(new_header(
+            "Access-Control-Allow-Methods",
+            "GET, POST, PUT, DELETE, OPTIONS",
+        ));
+        headers.push(new_header(
+            "Access-Control-Allow-Headers",
+            "Authorization, Content-Type",
+        ));
+        headers.push(new_header("Access-Control-Max-Age", "600"));
+
+        return (Ok((204, None)), headers);
+    }
+
+    let result = if access.authorized(request) {
+        handle(request)
+    } else {
+        Err(ApiError::new(401, "Missing or invalid token"))
+    };
+
+    (result, headers)
+}
+
+/// Turns the result of a request into a status and body, sending errors
+/// as `{"error": "..."}`.
+fn reply(result: ApiResult) -> (u16, Option<String>) {
+    match result {
+        Ok(response) => response,
+        Err(e) => (
+            e.status,
+            Some(serde_json::json!({ "error": e.message }).to_string()),
+        ),
+    }
+}
+
+fn json_header() -> Header {
+    new_header("Content-Type", "application/json")
+}
+
+fn new_header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field, value).expect("valid header")
+}
+
+/// The value of a request header, if it was sent.
+fn header<'a>(request: &'a Request, field: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(field))
+        .map(|h| h.value.as_str())
+}
+
+/// Routes a request to its endpoint.
+fn handle(request: &mut Request) -> ApiResult {
+    let (path, query) = split_url(request.url());
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(decode)
+        .collect::<Vec<String>>();
+    let segments = segments.iter().map(String::as_str).collect::<Vec<&str>>();
+
+    match (request.method(), segments.as_slice()) {
+        (Method::Get, ["notes"]) => list_notes(&query),
+        (Method::Post, ["notes"]) => create_note(read_json(request)?),
+        (Method::Get, ["notes", path @ ..]) => get_note(path),
+        (Method::Put, ["notes", path @ ..]) => update_note(path, read_json(request)?),
+        (Method::Delete, ["notes", path @ ..]) => delete_note(path),
+        (Method::Get, ["tags"]) => list_tags(),
+        (Method::Post, ["tags", path @ ..]) => {
+            let body: TagsBody = read_json(request)?;
+            let tags = tags::normalize_all(&body.tags)
+                .map_err(|e| ApiError::bad_request(e.to_string()))?;
+
+            update_tags(path, |note| note.add_tags(&tags))
+        }
+        (Method::Delete, ["tags", path @ ..]) => {
+            let body: TagsBody = read_json(request)?;
+            let tags = tags::normalize_query(&body.tags);
+
+            update_tags(path, |note| note.remove_tags(&tags))
+        }
+        _ => Err(ApiError::not_found("No such endpoint")),
+    }
+}
+
+/// Lists the notes matching the query parameters, which work like the
+/// options of `jottem find` (see [Query]).
+fn list_notes(query: &BTreeMap<String, String>) -> ApiResult {
+    let get = |key: &str| query.get(key).cloned();
+
+    let sort = query
+        .get("sort")
+        .map(|sort| SortOrder::from_str(sort, true))
+        .transpose()
+        .map_err(|_| ApiError::bad_request("Invalid sort order"))?;
+
+    let args = SearchArgs {
+        path: get("path"),
+        tags: query
+            .get("tags")
+            .map(|tags| {
+                tags.split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        glob: get("glob"),
+        regex: get("regex"),
+        folder: get("in"),
+        created_after: get("created_after"),
+        created_before: get("created_before"),
+        modified_after: get("modified_after"),
+        modified_before: get("modified_before"),
+        text: get("text"),
+        sort,
+        ..Default::default()
+    };
+
+    let query = Query::parse(&args).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let notes = query
+        .run(&Index::open()?)?
+        .into_iter()
+        .map(|hit| hit.note)
+        .collect::<Vec<Note>>();
+
+    json(200, &notes)
+}
+
+fn create_note(body: CreateBody) -> ApiResult {
+    let path = parse_path(&body.path)?;
+
+    if file::exists(&path) {
+        return Err(ApiError::new(
+            409,
+            format!("A note already exists at {}", path.relative_path()),
+        ));
+    }
+
+    let tags = tags::normalize_all(&body.tags).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let mut note = utils::create_note(&path, &tags)?;
+
+    if let Some(content) = body.content {
+        std::fs::write(&note.absolute_path, &content).map_err(anyhow::Error::from)?;
+        note.sync_content(&content);
+        note.touch();
+
+        let index = Index::open()?;
+        index.insert(&note)?;
+    }
+
+    json(201, &note)
+}
+
+fn get_note(path: &[&str]) -> ApiResult {
+    let note = find_note(path)?;
+    let content = std::fs::read_to_string(&note.absolute_path).map_err(anyhow::Error::from)?;
+
+    json(200, &NoteContent { note, content })
+}
+
+fn update_note(path: &[&str], body: UpdateBody) -> ApiResult {
+    let mut note = find_note(path)?;
+
+    std::fs::write(&note.absolute_path, &body.content).map_err(anyhow::Error::from)?;
+    note.sync_content(&body.content);
+    note.touch();
+
+    let index = Index::open()?;
+    index.insert(&note)?;
+
+    json(200, &note)
+}
+
+fn delete_note(path: &[&str]) -> ApiResult {
+    let note = find_note(path)?;
+
+    file::delete_file(&NotePath::from_note(&note)?)?;
+
+    let index = Index::open()?;
+    index.remove(note.id())?;
+
+    Ok((204, None))
+}
+
+fn list_tags() -> ApiResult {
+    let mut tags = BTreeMap::<String, usize>::new();
+
+    for note in Index::open()?.get_all()? {
+        for tag in note.tags {
+            *tags.entry(tag).or_default() += 1;
+        }
+    }
+
+    json(200, &tags)
+}
+
+fn update_tags(path: &[&str], update: impl FnOnce(&mut Note)) -> ApiResult {
+    let mut note = find_note(path)?;
+    update(&mut note);
+
+    let index = Index::open()?;
+    index.insert(&note)?;
+
+    json(200, &note)
+}
+
+/// Finds a note by its exact relative path. Unlike the CLI, we never prompt.
+fn find_note(path: &[&str]) -> Result<Note, ApiError> {
+    let path = parse_path(&path.join("/"))?;
+    let index = Index::open()?;
+
+    index
+        .find_by_path(&path)?
+        .pop()
+        .ok_or_else(|| ApiError::not_found(format!("No note at {}", path.relative_path())))
+}
+
+/// Parses a note path sent by a client. See [NotePath::parse_contained]
+fn parse_path(input: &str) -> Result<NotePath, ApiError> {
+    NotePath::parse_contained(input).map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// Reads a JSON request body. Other content types are rejected, since web
+/// pages can send those without a CORS preflight.
+fn read_json<T: serde::de::DeserializeOwned>(request: &mut Request) -> Result<T, ApiError> {
+    let is_json = header(request, "Content-Type").is_some_and(|value| {
+        value
+            .split(';')
+            .next()
+            .is_some_and(|t| t.trim().eq_ignore_ascii_case("application/json"))
+    });
+
+    if !is_json {
+        return Err(ApiError::new(
+            415,
+            "Request bodies must be sent as application/json",
+        ));
+    }
+
+    let mut body = String::new();
+
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE)
+        .read_to_string(&mut body)
+        .map_err(|e| ApiError::bad_request(format!("Failed to read request body: {e}")))?;
+
+    serde_json::from_str(&body)
+        .map_err(|e| ApiError::bad_request(format!("Invalid request body: {e}")))
+}
+
+fn json<T: Serialize>(status: u16, value: &T) -> ApiResult {
+    let body = serde_json::to_string(value).map_err(anyhow::Error::from)?;
+
+    Ok((status, Some(body)))
+}
+
+/// Splits a request URL into its path and decoded query parameters.
+fn split_url(url: &str) -> (&str, BTreeMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    let query = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (key, value) = p.split_once('=').unwrap_or((p, ""));
+            (
+                decode(&key.replace('+', " ")),
+                decode(&value.replace('+', " ")),
+            )
+        })
+        .collect();
+
+    (path, query)
+}
+
+/// Decodes `%XX` escapes in a URL component.
+fn decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use serial_test::serial;
+    use tiny_http::TestRequest;
+
+    use super::*;
+
+    #[test]
+    fn split_request_url() {
+        let (path, query) = split_url("/notes?path=foo%2Fbar&tags=a,b+c");
+
+        assert_eq!(path, "/notes");
+        assert_eq!(query.get("path").unwrap(), "foo/bar");
+        assert_eq!(query.get("tags").unwrap(), "a,b c");
+
+        assert_eq!(decode("my%20note%"), "my note%");
+    }
+
+    fn request(method: Method, path: &str, headers: &[(&str, &str)]) -> Request {
+        let mut request = TestRequest::new().with_method(method).with_path(path);
+
+        for (field, value) in headers {
+            request = request.with_header(new_header(field, value));
+        }
+
+        request.into()
+    }
+
+    fn status(request: &mut Request, access: &Access) -> u16 {
+        reply(process(request, access).0).0
+    }
+
+    #[test]
+    fn check_request_origin() {
+        let address = socket_address("127.0.0.1", 4000).unwrap();
+        let access = Access::new(address, None, vec!["http://app.test/".into()]);
+
+        let rebound = &[("Host", "evil.test:4000")];
+        assert_eq!(
+            status(&mut request(Method::Get, "/x", rebound), &access),
+            403
+        );
+
+        let cross_site = &[("Host", "localhost:4000"), ("Origin", "http://evil.test")];
+        assert_eq!(
+            status(&mut request(Method::Get, "/x", cross_site), &access),
+            403
+        );
+
+        let text = &[("Host", "[::1]:4000"), ("Content-Type", "text/plain")];
+        assert_eq!(
+            status(&mut request(Method::Post, "/notes", text), &access),
+            415
+        );
+
+        let preflight = &[("Host", "127.0.0.1:4000"), ("Origin", "http://app.test")];
+        let (result, headers) =
+            process(&mut request(Method::Options, "/notes", preflight), &access);
+
+        assert_eq!(reply(result).0, 204);
+        assert!(headers
+            .iter()
+            .any(|h| h.field.equiv("Access-Control-Allow-Origin")
+                && h.value.as_str() == "http://app.test"));
+
+        // pages served from the API's own host are always allowed
+        let same_origin = &[
+            ("Host", "localhost:4000"),
+            ("Origin", "http://localhost:4000"),
+        ];
+        assert_eq!(
+            status(&mut request(Method::Get, "/x", same_origin), &access),
+            404
+        );
+    }
+
+    /// Sends a request to a server using a temporary notes directory,
+    /// returning the status and JSON body.
+    fn call(
+        access: &Access,
+        method: Method,
+        path: &str,
+        body: Option<&'static str>,
+    ) -> (u16, serde_json::Value) {
+        let mut request = TestRequest::new()
+            .with_method(method)
+            .with_path(path)
+            .with_header(new_header("Host", "127.0.0.1:4000"));
+
+        if let Some(body) = body {
+            request = request.with_body(body).with_header(json_header());
+        }
+
+        if let Some(token) = &access.token {
+            request = request.with_header(new_header("Authorization", &format!("Bearer {token}")));
+        }
+
+        let (status, body) = reply(process(&mut request.into(), access).0);
+        let body = body.map_or(serde_json::Value::Null, |b| {
+            serde_json::from_str(&b).unwrap()
+        });
+
+        (status, body)
+    }
+
+    fn setup() -> (tempfile::TempDir, Access) {
+        let tmp = tempfile::tempdir().unwrap();
+        std::env::set_var("JOTTEM_ROOT", tmp.path());
+        std::env::set_var("JOTTEM_DB_PATH", tmp.path());
+
+        let address = socket_address("127.0.0.1", 4000).unwrap();
+
+        (tmp, Access::new(address, None, Vec::new()))
+    }
+
+    #[test]
+    #[serial]
+    fn note_endpoints() {
+        let (_tmp, access) = setup();
+
+        let body = r#"{"path": "work/plan", "tags": ["Project"], "content": "the budget"}"#;
+        let (status, note) = call(&access, Method::Post, "/notes", Some(body));
+        assert_eq!(status, 201);
+        assert_eq!(note["relative_path"], "work/plan");
+        assert_eq!(note["tags"], serde_json::json!(["project"]));
+
+        let (status, _) = call(&access, Method::Post, "/notes", Some(body));
+        assert_eq!(status, 409);
+
+        let (status, note) = call(&access, Method::Get, "/notes/work/plan", None);
+        assert_eq!(status, 200);
+        assert_eq!(note["content"], "the budget");
+
+        let body = r#"{"content": "new #idea"}"#;
+        let (status, note) = call(&access, Method::Put, "/notes/work/plan", Some(body));
+        assert_eq!(status, 200);
+        assert_eq!(note["inline_tags"], serde_json::json!(["idea"]));
+
+        let (status, notes) = call(&access, Method::Get, "/notes?text=idea&in=work", None);
+        assert_eq!(status, 200);
+        assert_eq!(notes.as_array().unwrap().len(), 1);
+
+        let (_, notes) = call(&access, Method::Get, "/notes?text=budget", None);
+        assert!(notes.as_array().unwrap().is_empty());
+
+        let (status, _) = call(&access, Method::Delete, "/notes/work/plan", None);
+        assert_eq!(status, 204);
+
+        let (status, _) = call(&access, Method::Get, "/notes/work/plan", None);
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    #[serial]
+    fn error_responses() {
+        let (_tmp, mut access) = setup();
+
+        let (status, body) = call(&access, Method::Get, "/notes/missing", None);
+        assert_eq!(status, 404);
+        assert_eq!(body["error"], "No note at missing");
+
+        let (status, body) = call(&access, Method::Post, "/notes", Some("{"));
+        assert_eq!(status, 400);
+        assert!(body["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid request body"));
+
+        let (status, body) = call(&access, Method::Get, "/notes/../secret", None);
+        assert_eq!(status, 400);
+        assert!(body["error"].is_string());
+
+        let (status, _) = call(&access, Method::Get, "/notes?sort=nope", None);
+        assert_eq!(status, 400);
+
+        access.token = Some("secret".into());
+        assert_eq!(call(&access, Method::Get, "/tags", None).0, 200);
+
+        let mut request: Request = TestRequest::new()
+            .with_path("/tags")
+            .with_header(new_header("Host", "127.0.0.1:4000"))
+            .into();
+        let (status, body) = reply(process(&mut request, &access).0);
+        assert_eq!(status, 401);
+        assert!(body.unwrap().contains("Missing or invalid token"));
+    }
+
+    #[test]
+    fn parse_bind_address() {
+        let address = socket_address("::1", 4000).unwrap();
+        assert_eq!(address.to_string(), "[::1]:4000");
+        assert!(address.ip().is_loopback());
+
+        assert_eq!(
+            socket_address("[::1]", 4000).unwrap().to_string(),
+            "[::1]:4000"
+        );
+        assert_eq!(
+            socket_address("127.0.0.1", 80).unwrap().to_string(),
+            "127.0.0.1:80"
+        );
+        assert!(socket_address("localhost", 4000)
+            .unwrap()
+            .ip()
+            .is_loopback());
+    }
+}
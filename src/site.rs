@@ -0,0 +1,532 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use pulldown_cmark::{html, CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+
+use crate::{index::Index, markdown, note::Note};
+
+const STYLESHEET: &str = "\
+body {
+    max-width: 48rem;
+    margin: 0 auto;
+    padding: 1rem;
+    font-family: system-ui, sans-serif;
+    line-height: 1.6;
+    color: #222;
+}
+nav, .meta {
+    font-size: 0.9rem;
+    color: #666;
+}
+nav a {
+    margin-right: 1rem;
+}
+a {
+    color: #0b62c4;
+}
+pre, code {
+    background: #f4f4f4;
+    border-radius: 3px;
+}
+pre {
+    padding: 0.75rem;
+    overflow-x: auto;
+}
+.tag {
+    margin-right: 0.5rem;
+}
+";
+
+/// Renders every note to a static HTML site.
+///
+/// * `out_dir` - directory to write the site into (created if missing)
+///
+/// The site is laid out as:
+/// * `index.html` - every note, tag and folder
+/// * `notes/<path>.html` - one page per note, mirroring the notes directory
+/// * `tags/<tag>.html` - the notes with each tag
+/// * `folders/<folder>.html` - the notes inside each folder
+/// * `style.css` - the default stylesheet
+///
+/// Links to other notes, either as relative `.md` links or `[[wikilinks]]`,
+/// are rewritten to point at the rendered pages.
+/// Returns the number of notes that were rendered.
+pub fn export(out_dir: &Path) -> anyhow::Result<usize> {
+    let mut notes = Index::open()?.get_all()?;
+    notes.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    let site = Site::new(&notes);
+
+    write(out_dir, "style.css", STYLESHEET)?;
+    write(out_dir, "index.html", &site.render_index())?;
+
+    for note in &notes {
+        let content = std::fs::read_to_string(&note.absolute_path).map_err(|e| {
+            anyhow::anyhow!("Failed to read note file: {}: {e}", note.absolute_path)
+        })?;
+
+        write(out_dir, &note_page(note), &site.render_note(note, &content))?;
+    }
+
+    for tag in site.tags.keys() {
+        write(out_dir, &tag_page(tag), &site.render_tag(tag))?;
+    }
+
+    for folder in &site.folders {
+        write(out_dir, &folder_page(folder), &site.render_folder(folder))?;
+    }
+
+    Ok(notes.len())
+}
+
+/// The notes being exported along with their tags and folders.
+struct Site<'a> {
+    notes: &'a [Note],
+    tags: BTreeMap<String, Vec<&'a Note>>,
+    folders: BTreeSet<String>,
+}
+
+impl<'a> Site<'a> {
+    fn new(notes: &'a [Note]) -> Self {
+        let mut tags = BTreeMap::<String, Vec<&Note>>::new();
+        let mut folders = BTreeSet::new();
+
+        for note in notes {
+            for tag in &note.tags {
+                tags.entry(tag.clone()).or_default().push(note);
+            }
+
+            // every ancestor of a note gets a folder page, not just its parent
+            let mut parent = note.relative_path.as_str();
+
+            while let Some((folder, _)) = parent.rsplit_once('/') {
+                folders.insert(folder.to_string());
+                parent = folder;
+            }
+        }
+
+        Self {
+            notes,
+            tags,
+            folders,
+        }
+    }
+
+    fn render_index(&self) -> String {
+        let root = root_prefix("index.html");
+        let mut body = String::from("<h1>Notes</h1>\n");
+
+        if !self.tags.is_empty() {
+            body.push_str("<h2>Tags</h2>\n<ul>\n");
+
+            for (tag, notes) in &self.tags {
+                body.push_str(&format!(
+                    "<li><a href=\"{root}{}\">{}</a> ({})</li>\n",
+                    encode(&tag_page(tag)),
+                    escape(tag),
+                    notes.len()
+                ));
+            }
+
+            body.push_str("</ul>\n");
+        }
+
+        if !self.folders.is_empty() {
+            body.push_str("<h2>Folders</h2>\n<ul>\n");
+
+            for folder in &self.folders {
+                body.push_str(&format!(
+                    "<li><a href=\"{root}{}\">{}</a></li>\n",
+                    encode(&folder_page(folder)),
+                    escape(folder)
+                ));
+            }
+
+            body.push_str("</ul>\n");
+        }
+
+        body.push_str("<h2>All notes</h2>\n");
+        body.push_str(&note_list(&root, self.notes.iter()));
+
+        layout("Notes", &root, &body)
+    }
+
+    fn render_note(&self, note: &Note, content: &str) -> String {
+        let root = root_prefix(&note_page(note));
+        let (_, body) = markdown::split_frontmatter(content);
+
+        let mut meta = format!("<p class=\"meta\">Modified {}", escape(&note.modified));
+
+        if let Some((folder, _)) = note.relative_path.rsplit_once('/') {
+            meta.push_str(&format!(
+                " in <a href=\"{root}{}\">{}</a>",
+                encode(&folder_page(folder)),
+                escape(folder)
+            ));
+        }
+
+        let mut tags = note.tags.iter().collect::<Vec<&String>>();
+        tags.sort();
+
+        for tag in tags {
+            meta.push_str(&format!(
+                " <a class=\"tag\" href=\"{root}{}\">#{}</a>",
+                encode(&tag_page(tag)),
+                escape(tag)
+            ));
+        }
+
+        meta.push_str("</p>\n");
+
+        let html = format!("{meta}{}", self.render_markdown(body, &root));
+
+        layout(&note.title, &root, &html)
+    }
+
+    fn render_tag(&self, tag: &str) -> String {
+        let root = root_prefix(&tag_page(tag));
+        let notes = self.tags.get(tag).map(Vec::as_slice).unwrap_or_default();
+
+        let body = format!(
+            "<h1>#{}</h1>\n{}",
+            escape(tag),
+            note_list(&root, notes.iter().copied())
+        );
+
+        layout(&format!("#{tag}"), &root, &body)
+    }
+
+    fn render_folder(&self, folder: &str) -> String {
+        let root = root_prefix(&folder_page(folder));
+        let prefix = format!("{folder}/");
+        let mut body = format!("<h1>{}/</h1>\n", escape(folder));
+
+        let subfolders = self
+            .folders
+            .iter()
+            .filter(|f| f.strip_prefix(&prefix).is_some_and(|f| !f.contains('/')))
+            .collect::<Vec<&String>>();
+
+        if !subfolders.is_empty() {
+            body.push_str("<ul>\n");
+
+            for subfolder in subfolders {
+                body.push_str(&format!(
+                    "<li><a href=\"{root}{}\">{}/</a></li>\n",
+                    encode(&folder_page(subfolder)),
+                    escape(subfolder)
+                ));
+            }
+
+            body.push_str("</ul>\n");
+        }
+
+        let notes = self
+            .notes
+            .iter()
+            .filter(|n| n.relative_path.starts_with(&prefix));
+
+        body.push_str(&note_list(&root, notes));
+
+        layout(folder, &root, &body)
+    }
+
+    /// Renders a note's markdown, rewriting links to other notes.
+    fn render_markdown(&self, body: &str, root: &str) -> String {
+        let mut options = Options::empty();
+        options.insert(Options::ENABLE_TABLES);
+        options.insert(Options::ENABLE_STRIKETHROUGH);
+        options.insert(Options::ENABLE_TASKLISTS);
+        options.insert(Options::ENABLE_WIKILINKS);
+
+        // unresolved wikilinks are rendered as plain text
+        let mut unresolved = false;
+
+        let events = Parser::new_ext(body, options).filter_map(|event| match event {
+            Event::Start(Tag::Link {
+                link_type: LinkType::WikiLink { .. },
+                dest_url,
+                title,
+                id,
+            }) => match self.resolve_wikilink(&dest_url) {
+                Some(dest_url) => Some(Event::Start(Tag::Link {
+                    link_type: LinkType::Inline,
+                    dest_url: format!("{root}{dest_url}").into(),
+                    title,
+                    id,
+                })),
+                None => {
+                    unresolved = true;
+                    None
+                }
+            },
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Some(Event::Start(Tag::Link {
+                link_type,
+                dest_url: rewrite_link(dest_url),
+                title,
+                id,
+            })),
+            Event::End(TagEnd::Link) if unresolved => {
+                unresolved = false;
+                None
+            }
+            event => Some(event),
+        });
+
+        let mut html = String::new();
+        html::push_html(&mut html, events);
+
+        html
+    }
+
+    /// Finds the page for a `[[wikilink]]` target, which can be a relative
+    /// path or the title of a note (if only one note has that title).
+    /// Aliases are treated as alternative titles.
+    fn resolve_wikilink(&self, target: &str) -> Option<String> {
+        let (target, fragment) = match target.split_once('#') {
+            Some((target, fragment)) => (target, format!("#{fragment}")),
+            None => (target, String::new()),
+        };
+
+        let target = target.trim().trim_matches('/');
+        let target = target.strip_suffix(".md").unwrap_or(target);
+
+        let note = self
+            .notes
+            .iter()
+            .find(|n| n.relative_path == target)
+            .or_else(|| {
+                let (parent, title) = match target.rsplit_once('/') {
+                    Some((parent, title)) => (Some(parent), title),
+                    None => (None, target),
+                };
+
+                let mut matches = self.notes.iter().filter(|n| {
+                    let in_parent = parent.is_none_or(|parent| {
+                        n.relative_path
+                            .rsplit_once('/')
+                            .is_some_and(|(p, _)| p == parent)
+                    });
+
+                    in_parent && n.has_title(title)
+                });
+
+                match (matches.next(), matches.next()) {
+                    (Some(note), None) => Some(note),
+                    _ => None,
+                }
+            })?;
+
+        Some(format!("{}{fragment}", encode(&note_page(note))))
+    }
+}
+
+/// Points relative links to `.md` files at the rendered `.html` pages.
+///
+/// Since note pages mirror the notes directory, relative links keep working.
+fn rewrite_link(dest_url: CowStr) -> CowStr {
+    if dest_url.contains("://") || dest_url.starts_with('/') || dest_url.starts_with("mailto:") {
+        return dest_url;
+    }
+
+    let (path, fragment) = match dest_url.split_once('#') {
+        Some((path, fragment)) => (path, format!("#{fragment}")),
+        None => (dest_url.as_ref(), String::new()),
+    };
+
+    match path.strip_suffix(".md") {
+        Some(path) => format!("{path}.html{fragment}").into(),
+        None => dest_url,
+    }
+}
+
+fn note_page(note: &Note) -> String {
+    format!("notes/{}.html", note.relative_path)
+}
+
+fn tag_page(tag: &str) -> String {
+    let levels = tag.split('/').map(tag_file_name).collect::<Vec<String>>();
+    format!("tags/{}.html", levels.join("/"))
+}
+
+/// Percent-encodes one level of a tag for use as a file name, so that tags
+/// kept from before normalization can't point outside the `tags` folder.
+fn tag_file_name(level: &str) -> String {
+    // `.` and `..` would refer to the current and parent folders
+    if level.chars().all(|c| c == '.') {
+        return level.replace('.', "%2E");
+    }
+
+    let mut encoded = String::with_capacity(level.len());
+
+    for c in level.chars() {
+        if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '~' | ' ' | '+' | '&') {
+            encoded.push(c);
+        } else {
+            let mut buf = [0; 4];
+
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{byte:02X}"));
+            }
+        }
+    }
+
+    encoded
+}
+
+fn folder_page(folder: &str) -> String {
+    format!("folders/{folder}.html")
+}
+
+/// Returns the relative path from a page back to the root of the site.
+fn root_prefix(page: &str) -> String {
+    "../".repeat(page.matches('/').count())
+}
+
+fn note_list<'a>(root: &str, notes: impl Iterator<Item = &'a Note>) -> String {
+    let mut html = String::from("<ul>\n");
+
+    for note in notes {
+        html.push_str(&format!(
+            "<li><a href=\"{root}{}\">{}</a></li>\n",
+            encode(&note_page(note)),
+            escape(&note.relative_path)
+        ));
+    }
+
+    html.push_str("</ul>\n");
+    html
+}
+
+fn layout(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{}</title>
+<link rel=\"stylesheet\" href=\"{root}style.css\">
+</head>
+<body>
+<nav><a href=\"{root}index.html\">Index</a></nav>
+<main>
+{body}</main>
+</body>
+</html>
+",
+        escape(title)
+    )
+}
+
+fn write(out_dir: &Path, page: &str, contents: &str) -> anyhow::Result<()> {
+    let path = out_dir.join(page);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to create directory: {}: {e}",
+                parent.to_string_lossy()
+            )
+        })?;
+    }
+
+    std::fs::write(&path, contents)
+        .map_err(|e| anyhow::anyhow!("Failed to write page: {}: {e}", path.to_string_lossy()))
+}
+
+/// Escapes text for use in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Percent-encodes a relative URL path, keeping the slashes.
+fn encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn note(relative_path: &str, tags: &[&str]) -> Note {
+        Note {
+            absolute_path: format!("/notes/{relative_path}.md"),
+            relative_path: relative_path.into(),
+            title: relative_path.rsplit('/').next().unwrap().into(),
+            created: String::new(),
+            modified: String::new(),
+            tags: tags
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<HashSet<String>>(),
+            inline_tags: HashSet::new(),
+            aliases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn collect_tags_and_folders() {
+        let notes = vec![note("a/b/c", &["x"]), note("d", &["x", "y"])];
+        let site = Site::new(&notes);
+
+        assert_eq!(site.tags.keys().collect::<Vec<_>>(), vec!["x", "y"]);
+        assert_eq!(site.tags["x"].len(), 2);
+        assert_eq!(site.folders.iter().collect::<Vec<_>>(), vec!["a", "a/b"]);
+    }
+
+    #[test]
+    fn rewrite_note_links() {
+        let mut notes = vec![note("projects/todo", &[]), note("ideas", &[])];
+        notes[0].aliases.push("tasks".into());
+        let site = Site::new(&notes);
+
+        let html = site.render_markdown(
+            "[todo](todo.md#today) [web](https://example.com/a.md) [[ideas]] [[projects/todo|Todo]] [[missing]] [[tasks]] [[projects/tasks]]",
+            "../../",
+        );
+
+        assert!(html.contains("href=\"todo.html#today\""));
+        assert!(html.contains("href=\"https://example.com/a.md\""));
+        assert!(html.contains("href=\"../../notes/ideas.html\">ideas</a>"));
+        assert!(html.contains("href=\"../../notes/projects/todo.html\">Todo</a>"));
+        assert!(html.contains(" missing "));
+        assert!(html.contains("href=\"../../notes/projects/todo.html\">tasks</a>"));
+        assert!(html.contains("href=\"../../notes/projects/todo.html\">projects/tasks</a>"));
+    }
+
+    #[test]
+    fn encode_page_paths() {
+        assert_eq!(root_prefix("notes/a/b.html"), "../../");
+        assert_eq!(root_prefix("index.html"), "");
+        assert_eq!(encode("tags/c++ & c.html"), "tags/c%2B%2B%20%26%20c.html");
+
+        assert_eq!(tag_page("c++ & c"), "tags/c++ & c.html");
+        assert_eq!(tag_page("project/alpha"), "tags/project/alpha.html");
+        assert_eq!(tag_page("../../x"), "tags/%2E%2E/%2E%2E/x.html");
+        assert_eq!(tag_page("a\\b:c"), "tags/a%5Cb%3Ac.html");
+    }
+}
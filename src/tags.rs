@@ -1,31 +1,441 @@
-use crate::{index::Index, utils};
+use std::collections::{BTreeMap, HashSet};
 
-/// Adds one or more tags to an existing note.
+use colored::Colorize;
+
+use crate::{config, index::Index, markdown, note::Note, tree, utils};
+
+/// Adds one or more tags to existing notes.
 ///
-/// * `path` - raw input from the user such as `foo/bar`
+/// * `path` - raw input from the user such as `foo/bar`. If omitted,
+///   the user chooses any number of notes to tag.
 /// * `tags` - a slice of String representing tags given by the user
-pub fn add_tags(path: &str, tags: &[String]) -> anyhow::Result<()> {
-    let note = utils::get_note(path, true)?;
-
-    let id = note.id();
+pub fn add_tags(path: Option<&str>, tags: &[String]) -> anyhow::Result<()> {
+    let tags = &normalize_all(tags)?;
+    let notes = utils::get_or_select_notes(path, true)?;
 
     let index = Index::open()?;
-    index.add_tags(id, tags)?;
+
+    for note in notes {
+        index.add_tags(note.id(), tags)?;
+    }
 
     Ok(())
 }
 
-/// Removes one or more tags from an existing note.
+/// Removes one or more tags from existing notes.
 ///
-/// * `path` - raw input from the user such as `foo/bar`
+/// * `path` - raw input from the user such as `foo/bar`. If omitted,
+///   the user chooses any number of notes to untag.
 /// * `tags` - a slice of String representing tags given by the user
-pub fn remove_tags(path: &str, tags: &[String]) -> anyhow::Result<()> {
-    let note = utils::get_note(path, false)?;
+pub fn remove_tags(path: Option<&str>, tags: &[String]) -> anyhow::Result<()> {
+    let tags = &normalize_query(tags);
+    let notes = utils::get_or_select_notes(path, false)?;
+
+    let index = Index::open()?;
+
+    for note in notes {
+        index.remove_tags(note.id(), tags)?;
+    }
+
+    Ok(())
+}
+
+/// Normalizes a tag given by the user, returning an error if it isn't valid.
+///
+/// Surrounding whitespace, a leading `#` and surrounding `/` are removed and,
+/// unless [config::get_tag_case] is `preserve`, the tag is lowercased.
+/// Valid tags only contain letters, numbers and [config::get_tag_chars].
+pub fn normalize(tag: &str) -> anyhow::Result<String> {
+    let tag = tag.trim().trim_start_matches('#').trim_matches('/');
+
+    let tag = match config::get_tag_case().as_str() {
+        "preserve" => tag.to_string(),
+        _ => tag.to_lowercase(),
+    };
+
+    if tag.is_empty() {
+        return Err(anyhow::anyhow!("Error: Tags can't be empty"));
+    }
+
+    let allowed = config::get_tag_chars();
+
+    if let Some(c) = tag
+        .chars()
+        .find(|&c| !c.is_alphanumeric() && !allowed.contains(c))
+    {
+        return Err(anyhow::anyhow!(
+            "Error: Invalid tag \"{tag}\": {c:?} is not allowed (only letters, numbers and \"{allowed}\")"
+        ));
+    }
+
+    if tag.split('/').any(str::is_empty) {
+        return Err(anyhow::anyhow!(
+            "Error: Invalid tag \"{tag}\": levels can't be empty"
+        ));
+    }
+
+    Ok(tag)
+}
+
+/// Normalizes a list of tags (see [normalize]), removing duplicates.
+pub fn normalize_all(tags: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::new();
+
+    for tag in tags {
+        let tag = normalize(tag)?;
+
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+
+    Ok(normalized)
+}
+
+/// Prepares tags used to look up or remove existing tags.
+///
+/// Both the tags as given and their normalized form are returned, so tags
+/// saved before normalization (or that are now invalid) can still be found.
+pub fn normalize_query(tags: &[String]) -> Vec<String> {
+    let mut query = tags.to_vec();
+    query.extend(tags.iter().filter_map(|t| normalize(t).ok()));
+    query.sort();
+    query.dedup();
+
+    query
+}
+
+/// Turns any string into a valid tag for migrating existing tags, by
+/// replacing characters that aren't allowed with `-`.
+///
+/// Returns `None` if nothing is left of the tag.
+fn sanitize(tag: &str) -> Option<String> {
+    if let Ok(tag) = normalize(tag) {
+        return Some(tag);
+    }
+
+    let allowed = config::get_tag_chars();
+    let replacement = if allowed.contains('-') { "-" } else { "" };
 
-    let id = note.id();
+    let levels = tag
+        .split('/')
+        .map(|level| {
+            level
+                .trim()
+                .split(|c: char| !c.is_alphanumeric() && (c == '/' || !allowed.contains(c)))
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<&str>>()
+                .join(replacement)
+        })
+        .filter(|level| !level.is_empty())
+        .collect::<Vec<String>>();
+
+    let separator = if allowed.contains('/') {
+        "/"
+    } else {
+        replacement
+    };
+
+    normalize(&levels.join(separator)).ok()
+}
 
+/// Normalizes the tags of every note in the index (see [normalize]).
+///
+/// * `dry_run` - only print the changes that would be made
+///
+/// Tags that aren't valid have their disallowed characters replaced with
+/// `-`, and tags with nothing left are removed.
+pub fn normalize_index(dry_run: bool) -> anyhow::Result<()> {
     let index = Index::open()?;
-    index.remove_tags(id, tags)?;
+    let mut count = 0;
+
+    for mut note in index.get_all()? {
+        let mut changes = Vec::new();
+
+        let mut migrate = |tags: &HashSet<String>, log: bool| -> HashSet<String> {
+            tags.iter()
+                .filter_map(|tag| {
+                    let new = sanitize(tag);
+
+                    if log && new.as_ref() != Some(tag) {
+                        changes.push((tag.clone(), new.clone()));
+                    }
+
+                    new
+                })
+                .collect()
+        };
+
+        let tags = migrate(&note.tags, true);
+        let inline_tags = migrate(&note.inline_tags, false);
+        let manual_tags = migrate(&(&note.tags - &note.inline_tags), false);
+
+        if changes.is_empty() {
+            continue;
+        }
+
+        changes.sort();
+
+        for (old, new) in changes {
+            let new = new.unwrap_or_else(|| "(removed)".to_string());
+            println!("{}: {old} -> {new}", note.relative_path);
+        }
+
+        note.tags = tags;
+        // an inline tag that now clashes with a manual tag is kept as manual
+        note.inline_tags = &inline_tags - &manual_tags;
+
+        if !dry_run {
+            index.insert(&note)?;
+        }
+
+        count += 1;
+    }
+
+    let message = if dry_run {
+        format!("Would normalize tags on {count} notes")
+    } else {
+        format!("Normalized tags on {count} notes")
+    };
+
+    println!("{}", message.bright_green());
 
     Ok(())
 }
+
+/// Returns true if `tag` is `query` or one of its descendants.
+///
+/// Tags are hierarchical, with levels separated by `/`, so the query
+/// `project` matches `project` and `project/alpha/design` but not `projects`.
+pub fn matches(tag: &str, query: &str) -> bool {
+    let query = query.trim_matches('/');
+
+    tag.strip_prefix(query)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Returns the new name for `tag` when `old` is renamed to `new`, or `None`
+/// if the tag isn't `old` or one of its descendants.
+pub fn renamed(tag: &str, old: &str, new: &str) -> Option<String> {
+    matches(tag, old).then(|| format!("{new}{}", &tag[old.len()..]))
+}
+
+/// A level in the tag hierarchy.
+///
+/// * `tags` - child tags keyed by their name (sorted for stable output)
+/// * `count` - number of notes with this tag or any of its descendants
+#[derive(Debug, Default)]
+pub struct TagTree {
+    pub tags: BTreeMap<String, TagTree>,
+    pub count: usize,
+}
+
+impl TagTree {
+    /// Builds the tag hierarchy from the tags of every note.
+    pub fn from_notes(notes: &[Note]) -> Self {
+        let mut root = TagTree::default();
+
+        for note in notes {
+            // a note with `a/b` and `a/c` still only counts once towards `a`
+            let mut counted = HashSet::new();
+
+            for tag in &note.tags {
+                let mut level = &mut root;
+                let mut path = String::new();
+
+                for segment in tag.split('/').filter(|s| !s.is_empty()) {
+                    path.push_str(segment);
+                    path.push('/');
+
+                    level = level.tags.entry(segment.to_string()).or_default();
+
+                    if counted.insert(path.clone()) {
+                        level.count += 1;
+                    }
+                }
+            }
+        }
+
+        root
+    }
+
+    /// Renders the hierarchy as an indented tree, in the style of `jottem tree`.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        for (name, child) in &self.tags {
+            out.push_str(&format!("{} ({})\n", name.yellow(), child.count));
+            child.render_children("", &mut out);
+        }
+
+        out
+    }
+
+    fn render_children(&self, prefix: &str, out: &mut String) {
+        let total = self.tags.len();
+
+        for (position, (name, child)) in self.tags.iter().enumerate() {
+            let (branch, indent) = tree::branch(position + 1 == total);
+
+            out.push_str(&format!(
+                "{prefix}{branch}{} ({})\n",
+                name.yellow(),
+                child.count
+            ));
+            child.render_children(&format!("{prefix}{indent}"), out);
+        }
+    }
+}
+
+/// Prints every tag as a tree, along with the number of notes using it.
+pub fn list_tags() -> anyhow::Result<()> {
+    let index = Index::open()?;
+    let tree = TagTree::from_notes(&index.get_all()?);
+
+    if tree.tags.is_empty() {
+        println!("{}", "No tags found".bright_red());
+    } else {
+        print!("{}", tree.render());
+    }
+
+    Ok(())
+}
+
+/// Renames a tag, along with all of its descendants, on every note.
+///
+/// * `old` - the tag to rename, such as `project`
+/// * `new` - its new name, such as `work/project`
+///
+/// Inline `#hashtags` are renamed in the note files too, so they stay in
+/// sync with the index. Every file is read before any of them is changed,
+/// so an unreadable note leaves everything as it was.
+pub fn rename_tag(old: &str, new: &str) -> anyhow::Result<()> {
+    let trimmed = old.trim().trim_start_matches('#').trim_matches('/');
+    let new = &normalize(new)?;
+
+    if trimmed.is_empty() {
+        return Err(anyhow::anyhow!("Error: No tag provided"));
+    }
+
+    // tags saved before normalization may only match the tag as given
+    let olds = normalize_query(&[trimmed.to_string()]);
+    let rename_one = |tag: &str| olds.iter().find_map(|old| renamed(tag, old, new));
+
+    let index = Index::open()?;
+    let mut changes = Vec::new();
+
+    for mut note in index.get_all()? {
+        if !note.tags.iter().any(|t| rename_one(t).is_some()) {
+            continue;
+        }
+
+        let rename = |tags: &HashSet<String>| -> HashSet<String> {
+            tags.iter()
+                .map(|t| rename_one(t).unwrap_or_else(|| t.clone()))
+                .collect()
+        };
+
+        note.tags = rename(&note.tags);
+        note.inline_tags = rename(&note.inline_tags);
+
+        let content = std::fs::read_to_string(&note.absolute_path).map_err(|e| {
+            anyhow::anyhow!("Failed to read note file: {}: {e}", note.absolute_path)
+        })?;
+        let (_, body) = markdown::split_frontmatter(&content);
+        let frontmatter = &content[..content.len() - body.len()];
+        // hashtags are read back normalized, so `#Project` is `project` too
+        let body = markdown::rename_hashtags(body, |tag| {
+            rename_one(tag).or_else(|| rename_one(&normalize(tag).ok()?))
+        });
+        let renamed = format!("{frontmatter}{body}");
+
+        // only rewrite files with hashtags to rename
+        let content = (renamed != content).then_some(renamed);
+
+        changes.push((note, content));
+    }
+
+    for (note, content) in &changes {
+        if let Some(content) = content {
+            std::fs::write(&note.absolute_path, content).map_err(|e| {
+                anyhow::anyhow!("Failed to write note file: {}: {e}", note.absolute_path)
+            })?;
+        }
+
+        index.insert(note)?;
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Renamed {} to {new} on {} notes",
+            normalize(trimmed).unwrap_or_else(|_| trimmed.to_string()),
+            changes.len()
+        )
+        .bright_green()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::path::NotePath;
+
+    #[test]
+    fn match_hierarchical_tags() {
+        assert!(matches("project", "project"));
+        assert!(matches("project/alpha/design", "project"));
+        assert!(matches("project/alpha/design", "project/alpha/"));
+        assert!(!matches("projects", "project"));
+        assert!(!matches("project", "project/alpha"));
+
+        assert_eq!(
+            renamed("project/alpha", "project", "work"),
+            Some("work/alpha".into())
+        );
+        assert_eq!(renamed("projects", "project", "work"), None);
+    }
+
+    #[test]
+    fn normalize_tags() {
+        assert_eq!(normalize(" #Work ").unwrap(), "work");
+        assert_eq!(normalize("Project/Alpha/").unwrap(), "project/alpha");
+        assert!(normalize("").is_err());
+        assert!(normalize("  ").is_err());
+        assert!(normalize("two words").is_err());
+        assert!(normalize("a//b").is_err());
+
+        let tags = ["Work".to_string(), "work".into(), " work".into()];
+        assert_eq!(normalize_all(&tags).unwrap(), vec!["work"]);
+        assert_eq!(normalize_query(&tags), vec![" work", "Work", "work"]);
+
+        assert_eq!(sanitize("Two  Words!"), Some("two-words".into()));
+        assert_eq!(sanitize("a / b"), Some("a/b".into()));
+        assert_eq!(sanitize(" !! "), None);
+    }
+
+    #[test]
+    fn build_tag_tree() {
+        let notes = vec![
+            Note::new(
+                &NotePath::parse("a").unwrap(),
+                &["project/alpha".into(), "project/beta".into()],
+            ),
+            Note::new(
+                &NotePath::parse("b").unwrap(),
+                &["project/alpha/design".into(), "work".into()],
+            ),
+        ];
+
+        let tree = TagTree::from_notes(&notes);
+        let project = &tree.tags["project"];
+
+        assert_eq!(project.count, 2);
+        assert_eq!(project.tags["alpha"].count, 2);
+        assert_eq!(project.tags["alpha"].tags["design"].count, 1);
+        assert_eq!(project.tags["beta"].count, 1);
+        assert_eq!(tree.tags["work"].count, 1);
+    }
+}
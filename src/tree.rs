@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use colored::Colorize;
+
+use crate::{cli::TreeArgs, note::Note, path::NotePath};
+
+/// A folder in the notes hierarchy, built from the parents of each [NotePath].
+///
+/// * `folders` - subfolders keyed by their name (sorted for stable output)
+/// * `notes` - notes stored directly inside this folder
+#[derive(Debug, Default)]
+pub struct Folder {
+    pub folders: BTreeMap<String, Folder>,
+    pub notes: Vec<Note>,
+}
+
+impl Folder {
+    /// Builds the full folder hierarchy from a flat list of notes.
+    pub fn from_notes(notes: Vec<Note>) -> anyhow::Result<Self> {
+        let mut root = Folder::default();
+
+        for note in notes {
+            let path = NotePath::from_note(&note)?;
+            let mut folder = &mut root;
+
+            if let Some(parent) = path.relative_parent() {
+                for segment in parent.split('/') {
+                    folder = folder.folders.entry(segment.to_string()).or_default();
+                }
+            }
+
+            folder.notes.push(note);
+        }
+
+        root.sort();
+
+        Ok(root)
+    }
+
+    /// Finds a subfolder by its relative path (such as `foo/bar`).
+    pub fn get(&self, path: &str) -> Option<&Folder> {
+        let path = path.trim_matches('/');
+
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        path.split('/')
+            .try_fold(self, |folder, segment| folder.folders.get(segment))
+    }
+
+    /// Total number of notes in this folder and all of its subfolders.
+    pub fn count(&self) -> usize {
+        self.notes.len() + self.folders.values().map(Folder::count).sum::<usize>()
+    }
+
+    fn sort(&mut self) {
+        self.notes.sort_by(|a, b| a.title.cmp(&b.title));
+        self.folders.values_mut().for_each(Folder::sort);
+    }
+}
+
+/// Renders a folder as an indented tree, in the style of the `tree` utility.
+///
+/// * `name` - label printed for the top-level folder
+/// * `args` - display options. See [cli::TreeArgs](crate::cli::TreeArgs)
+///
+/// Folders are listed before notes and are annotated with the total number
+/// of notes they contain. Folders nested deeper than `args.depth` are shown
+/// collapsed, with only their note count. A depth of 0 only shows the
+/// top-level folder.
+pub fn render(folder: &Folder, name: &str, args: &TreeArgs) -> String {
+    let mut out = format!("{} ({})\n", name.blue().bold(), folder.count());
+
+    if args.depth != Some(0) {
+        render_children(folder, args, "", 1, &mut out);
+    }
+
+    out
+}
+
+fn render_children(folder: &Folder, args: &TreeArgs, prefix: &str, depth: usize, out: &mut String) {
+    let total = folder.folders.len() + folder.notes.len();
+    let mut position = 0;
+
+    for (name, child) in &folder.folders {
+        position += 1;
+        let (branch, indent) = branch(position == total);
+
+        out.push_str(&format!(
+            "{prefix}{branch}{} ({})\n",
+            name.blue().bold(),
+            child.count()
+        ));
+
+        if args.depth.is_none_or(|max| depth < max) {
+            render_children(child, args, &format!("{prefix}{indent}"), depth + 1, out);
+        }
+    }
+
+    for note in &folder.notes {
+        position += 1;
+        let (branch, _) = branch(position == total);
+
+        out.push_str(&format!("{prefix}{branch}{}", note.title));
+
+        if args.tags && !note.tags.is_empty() {
+            let mut tags = note.tags.iter().map(String::as_str).collect::<Vec<&str>>();
+            tags.sort();
+            out.push_str(&format!("  {}", format!("[{}]", tags.join(", ")).yellow()));
+        }
+
+        if args.modified {
+            out.push_str(&format!("  {}", note.modified.dimmed()));
+        }
+
+        out.push('\n');
+    }
+}
+
+/// Returns the branch drawn before an entry and the indentation used for its children.
+pub(crate) fn branch(last: bool) -> (&'static str, &'static str) {
+    if last {
+        ("└── ", "    ")
+    } else {
+        ("├── ", "│   ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn notes(paths: &[&str]) -> Vec<Note> {
+        paths
+            .iter()
+            .map(|p| Note::new(&NotePath::parse(p).unwrap(), &["tag".into()]))
+            .collect()
+    }
+
+    fn args(depth: Option<usize>, tags: bool) -> TreeArgs {
+        TreeArgs {
+            folder: None,
+            depth,
+            tags,
+            modified: false,
+        }
+    }
+
+    #[test]
+    fn build_folder_hierarchy() {
+        let root = Folder::from_notes(notes(&["a", "foo/b", "foo/bar/c", "foo/bar/d"])).unwrap();
+
+        assert_eq!(root.count(), 4);
+        assert_eq!(root.notes.len(), 1);
+        assert_eq!(root.get("foo").unwrap().count(), 3);
+        assert_eq!(root.get("foo/bar/").unwrap().notes.len(), 2);
+        assert!(root.get("foo/baz").is_none());
+    }
+
+    #[test]
+    fn render_tree() {
+        colored::control::set_override(false);
+
+        let root = Folder::from_notes(notes(&["a", "foo/b", "foo/bar/c"])).unwrap();
+
+        let expected = "\
+notes (3)
+├── foo (2)
+│   ├── bar (1)
+│   │   └── c  [tag]
+│   └── b  [tag]
+└── a  [tag]
+";
+        assert_eq!(render(&root, "notes", &args(None, true)), expected);
+
+        let expected = "\
+notes (3)
+├── foo (2)
+└── a
+";
+        assert_eq!(render(&root, "notes", &args(Some(1), false)), expected);
+        assert_eq!(render(&root, "notes", &args(Some(0), false)), "notes (3)\n");
+    }
+}
@@ -0,0 +1,534 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{file, index::Index, note::Note, path::NotePath, tags, utils};
+
+/// A filter typed into the note list, split into its search terms.
+///
+/// Terms are separated by whitespace and must all match (AND):
+/// * `#tag` - the note has a tag starting with `tag`
+/// * `/text` - the note content contains `text`
+/// * anything else - the relative path contains the term
+///
+/// All comparisons are case-insensitive.
+#[derive(Debug, Default, PartialEq)]
+pub struct Filter {
+    pub paths: Vec<String>,
+    pub tags: Vec<String>,
+    pub text: Vec<String>,
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Self {
+        let mut filter = Self::default();
+
+        for term in input.split_whitespace() {
+            let term = term.to_lowercase();
+
+            if let Some(tag) = term.strip_prefix('#') {
+                filter.tags.push(tag.to_string());
+            } else if let Some(text) = term.strip_prefix('/') {
+                filter.text.push(text.to_string());
+            } else {
+                filter.paths.push(term);
+            }
+        }
+
+        filter
+    }
+
+    /// Returns true if the filter needs the content of each note.
+    pub fn needs_content(&self) -> bool {
+        !self.text.is_empty()
+    }
+
+    /// * `content` - lowercased note content, only required for text terms
+    pub fn matches(&self, note: &Note, content: Option<&str>) -> bool {
+        let path = note.relative_path.to_lowercase();
+
+        let paths = self.paths.iter().all(|p| path.contains(p.as_str()));
+        let tags = self.tags.iter().all(|t| {
+            note.tags
+                .iter()
+                .any(|tag| tag.to_lowercase().starts_with(t.as_str()))
+        });
+        let text = self
+            .text
+            .iter()
+            .all(|t| content.is_some_and(|c| c.contains(t.as_str())));
+
+        paths && tags && text
+    }
+}
+
+/// What keyboard input currently applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Filter,
+    Tag,
+    Move,
+    ConfirmDelete,
+}
+
+/// State of the note browser.
+struct App {
+    notes: Vec<Note>,
+    /// Indices into `notes` of the notes matching the filter
+    visible: Vec<usize>,
+    list: ListState,
+    mode: Mode,
+    filter: String,
+    input: String,
+    status: String,
+    /// Lowercased note contents, loaded on demand for text filters
+    contents: HashMap<u64, String>,
+    /// Content of the previewed note, keyed by its id
+    preview: Option<(u64, String)>,
+    quit: bool,
+}
+
+impl App {
+    fn new() -> anyhow::Result<Self> {
+        let mut app = Self {
+            notes: Vec::new(),
+            visible: Vec::new(),
+            list: ListState::default(),
+            mode: Mode::Normal,
+            filter: String::new(),
+            input: String::new(),
+            status: String::new(),
+            contents: HashMap::new(),
+            preview: None,
+            quit: false,
+        };
+
+        app.reload()?;
+
+        Ok(app)
+    }
+
+    /// Reloads every note from the index and reapplies the filter.
+    fn reload(&mut self) -> anyhow::Result<()> {
+        let selected = self.selected().map(|n| n.id());
+
+        self.notes = Index::open()?.get_all()?;
+        self.notes
+            .sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        self.contents.clear();
+        self.preview = None;
+        self.apply_filter();
+
+        if let Some(id) = selected {
+            if let Some(pos) = self.visible.iter().position(|&i| self.notes[i].id() == id) {
+                self.list.select(Some(pos));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = Filter::parse(&self.filter);
+
+        if filter.needs_content() {
+            for note in &self.notes {
+                self.contents.entry(note.id()).or_insert_with(|| {
+                    std::fs::read_to_string(&note.absolute_path)
+                        .unwrap_or_default()
+                        .to_lowercase()
+                });
+            }
+        }
+
+        self.visible = self
+            .notes
+            .iter()
+            .enumerate()
+            .filter(|(_, note)| {
+                let content = self.contents.get(&note.id()).map(String::as_str);
+                filter.matches(note, content)
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        let selected = self.list.selected().unwrap_or_default();
+
+        self.list.select(match self.visible.len() {
+            0 => None,
+            len => Some(selected.min(len - 1)),
+        });
+    }
+
+    fn selected(&self) -> Option<&Note> {
+        self.list
+            .selected()
+            .and_then(|i| self.visible.get(i))
+            .map(|&i| &self.notes[i])
+    }
+
+    /// Returns the content of the selected note, only reading its file when
+    /// the selection changes.
+    fn preview_content(&mut self) -> &str {
+        let Some(note) = self.selected() else {
+            return "";
+        };
+
+        let id = note.id();
+
+        if self.preview.as_ref().map(|(i, _)| *i) != Some(id) {
+            let content = std::fs::read_to_string(&note.absolute_path).unwrap_or_default();
+            self.preview = Some((id, content));
+        }
+
+        self.preview.as_ref().map_or("", |(_, c)| c.as_str())
+    }
+
+    fn handle_key(&mut self, terminal: &mut DefaultTerminal, key: KeyEvent) -> anyhow::Result<()> {
+        match self.mode {
+            Mode::Normal => self.handle_normal(terminal, key)?,
+            Mode::Filter => match key.code {
+                KeyCode::Enter => self.mode = Mode::Normal,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.apply_filter();
+                    self.mode = Mode::Normal;
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.apply_filter();
+                }
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.apply_filter();
+                }
+                _ => {}
+            },
+            Mode::Tag | Mode::Move => match key.code {
+                KeyCode::Enter => {
+                    let mode = self.mode;
+                    self.mode = Mode::Normal;
+
+                    let result = match mode {
+                        Mode::Tag => self.tag_selected(),
+                        _ => self.move_selected(),
+                    };
+
+                    if let Err(e) = result {
+                        self.status = e.to_string();
+                    }
+
+                    self.reload()?;
+                }
+                KeyCode::Esc => self.mode = Mode::Normal,
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                _ => {}
+            },
+            Mode::ConfirmDelete => {
+                self.mode = Mode::Normal;
+
+                if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+                    if let Err(e) = self.delete_selected() {
+                        self.status = e.to_string();
+                    }
+
+                    self.reload()?;
+                } else {
+                    self.status = "Cancelled".to_string();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_normal(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        key: KeyEvent,
+    ) -> anyhow::Result<()> {
+        self.status.clear();
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Home | KeyCode::Char('g') => self.list.select_first(),
+            KeyCode::End | KeyCode::Char('G') => self.list.select_last(),
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('r') => self.reload()?,
+            KeyCode::Enter | KeyCode::Char('e') if self.selected().is_some() => {
+                self.edit_selected(terminal)?;
+                self.reload()?;
+            }
+            KeyCode::Char('t') if self.selected().is_some() => {
+                self.input.clear();
+                self.mode = Mode::Tag;
+            }
+            KeyCode::Char('m') if self.selected().is_some() => {
+                self.input = self.selected().unwrap().relative_path.clone();
+                self.mode = Mode::Move;
+            }
+            KeyCode::Char('d') if self.selected().is_some() => self.mode = Mode::ConfirmDelete,
+            _ => {}
+        }
+
+        // keep the selection inside the list after jumping past either end
+        if let Some(selected) = self.list.selected() {
+            self.list
+                .select(Some(selected.min(self.visible.len().saturating_sub(1))));
+        }
+
+        Ok(())
+    }
+
+    /// Opens the selected note in the editor, suspending the terminal UI meanwhile.
+    fn edit_selected(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        let Some(mut note) = self.selected().cloned() else {
+            return Ok(());
+        };
+
+        ratatui::restore();
+        let changed = utils::open_note(&note.absolute_path);
+        *terminal = ratatui::init();
+
+        if changed? {
+            utils::sync_note(&mut note)?;
+            note.touch();
+
+            let index = Index::open()?;
+            index.insert(&note)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the tags typed by the user to the selected note.
+    ///
+    /// Tags are comma-separated. Tags prefixed with `-` are removed instead of added.
+    fn tag_selected(&mut self) -> anyhow::Result<()> {
+        let Some(note) = self.selected() else {
+            return Ok(());
+        };
+
+        let (remove, add): (Vec<String>, Vec<String>) = self
+            .input
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .partition(|t| t.starts_with('-'));
+
+        let remove = remove
+            .into_iter()
+            .map(|t| t.trim_start_matches('-').to_string())
+            .collect::<Vec<String>>();
+
+        let add = tags::normalize_all(&add)?;
+        let remove = tags::normalize_query(&remove);
+
+        let index = Index::open()?;
+        index.add_tags(note.id(), &add)?;
+        index.remove_tags(note.id(), &remove)?;
+
+        Ok(())
+    }
+
+    /// Moves the selected note to the relative path typed by the user.
+    fn move_selected(&mut self) -> anyhow::Result<()> {
+        let Some(note) = self.selected().cloned() else {
+            return Ok(());
+        };
+
+        let new_path = NotePath::parse(self.input.trim())?;
+
+        if new_path.relative_path() == note.relative_path {
+            return Ok(());
+        }
+
+        if file::exists(&new_path) {
+            return Err(anyhow::anyhow!(
+                "A note already exists at {}",
+                new_path.relative_path()
+            ));
+        }
+
+        crate::relocate_note(note, &new_path)?;
+        self.status = format!("Moved to {}", new_path.relative_path());
+
+        Ok(())
+    }
+
+    fn delete_selected(&mut self) -> anyhow::Result<()> {
+        let Some(note) = self.selected().cloned() else {
+            return Ok(());
+        };
+
+        file::delete_file(&NotePath::from_note(&note)?)?;
+
+        let index = Index::open()?;
+        index.remove(note.id())?;
+
+        self.status = format!("Deleted {}", note.relative_path);
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let [left, preview] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .areas(main);
+
+        let [filter, list] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(1)])
+            .areas(left);
+
+        let filter_style = if self.mode == Mode::Filter {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+
+        frame.render_widget(
+            Paragraph::new(self.filter.as_str()).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(filter_style)
+                    .title("Filter (path #tag /text)"),
+            ),
+            filter,
+        );
+
+        let items = self
+            .visible
+            .iter()
+            .map(|&i| {
+                let note = &self.notes[i];
+                let mut tags = note.tags.iter().map(String::as_str).collect::<Vec<&str>>();
+                tags.sort();
+
+                let mut spans = vec![Span::raw(note.relative_path.as_str())];
+
+                if !tags.is_empty() {
+                    spans.push(Span::styled(
+                        format!("  [{}]", tags.join(", ")),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+
+                ListItem::new(Line::from(spans))
+            })
+            .collect::<Vec<ListItem>>();
+
+        let list_widget = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Notes ({}/{})",
+                self.visible.len(),
+                self.notes.len()
+            )))
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            );
+
+        frame.render_stateful_widget(list_widget, list, &mut self.list);
+
+        let title = match self.selected() {
+            Some(note) => format!("{}  ({})", note.relative_path, note.modified),
+            None => "Preview".to_string(),
+        };
+        let content = self.preview_content();
+
+        frame.render_widget(
+            Paragraph::new(content)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(title)),
+            preview,
+        );
+
+        let footer_text = match self.mode {
+            Mode::Normal if !self.status.is_empty() => self.status.clone(),
+            Mode::Normal => {
+                "q quit  / filter  enter/e edit  t tag  m move  d delete  r reload".to_string()
+            }
+            Mode::Filter => "enter apply  esc clear".to_string(),
+            Mode::Tag => format!("Tags (a,b,-c removes c): {}", self.input),
+            Mode::Move => format!("New path: {}", self.input),
+            Mode::ConfirmDelete => "Delete this note? (y/n)".to_string(),
+        };
+
+        frame.render_widget(
+            Paragraph::new(footer_text).style(Style::default().fg(Color::Cyan)),
+            footer,
+        );
+    }
+}
+
+/// Runs the full-screen note browser until the user quits.
+pub fn run() -> anyhow::Result<()> {
+    let mut app = App::new()?;
+    let mut terminal = ratatui::init();
+
+    let result = event_loop(&mut app, &mut terminal);
+
+    ratatui::restore();
+
+    result
+}
+
+fn event_loop(app: &mut App, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(terminal, key)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn filter_notes() {
+        let path = NotePath::parse("Projects/alpha/todo").unwrap();
+        let note = Note::new(&path, &["Work".into(), "urgent".into()]);
+
+        assert!(Filter::parse("").matches(&note, None));
+        assert!(Filter::parse("alpha TODO").matches(&note, None));
+        assert!(Filter::parse("#work #urg").matches(&note, None));
+        assert!(!Filter::parse("#home").matches(&note, None));
+        assert!(!Filter::parse("beta").matches(&note, None));
+
+        let filter = Filter::parse("alpha /milk");
+        assert!(filter.needs_content());
+        assert!(filter.matches(&note, Some("buy milk")));
+        assert!(!filter.matches(&note, Some("buy bread")));
+        assert!(!filter.matches(&note, None));
+    }
+}
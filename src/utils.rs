@@ -1,12 +1,35 @@
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
 use colored::Colorize;
 use comfy_table::{presets::ASCII_MARKDOWN, Cell, Table};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 
-use crate::{config, file, index::Index, note::Note, path::NotePath, prompt};
+use crate::{
+    cli::{OutputArgs, OutputFormat},
+    config, file,
+    index::Index,
+    note::Note,
+    path::{self, NotePath},
+    prompt,
+    search::Hit,
+    tags,
+};
+
+/// The most similar notes offered when nothing matches exactly.
+const MAX_SIMILAR: usize = 20;
+
+static EXACT: AtomicBool = AtomicBool::new(false);
+
+/// Requires note lookups to match a title or path exactly, disabling the
+/// fallback to similar notes. Set by `--exact`.
+pub fn set_exact(exact: bool) {
+    EXACT.store(exact, Ordering::Relaxed);
+}
 
 /// Creates the root note directory and initializes it as a git repository
 ///
@@ -27,7 +50,10 @@ pub fn create_root_dir() -> anyhow::Result<()> {
 }
 
 /// Creates an ASCII table for displaying a collection of notes.
-pub fn build_table(notes: Vec<Note>) -> String {
+///
+/// * `scores` - relevance scores for each note, in the same order, shown
+///   in an extra column if given
+pub fn build_table(notes: Vec<Note>, scores: Option<&[f64]>) -> String {
     let mut table = Table::new();
 
     // Creates a simple ASCII table with the outer borders removed (aesthetic choice)
@@ -39,18 +65,60 @@ pub fn build_table(notes: Vec<Note>) -> String {
         .set_style(comfy_table::TableComponent::RightHeaderIntersection, '\0');
 
     // A splash of color on the headings for extra clarity. May be changed later.
-    table.set_header(vec![
+    let mut header = vec![
         Cell::new("Note").fg(comfy_table::Color::Cyan),
         Cell::new("Modified Time").fg(comfy_table::Color::Cyan),
-    ]);
+    ];
 
-    for note in notes {
-        table.add_row(vec![note.relative_path, note.modified]);
+    if scores.is_some() {
+        header.push(Cell::new("Score").fg(comfy_table::Color::Cyan));
+    }
+
+    table.set_header(header);
+
+    for (i, note) in notes.into_iter().enumerate() {
+        let mut row = vec![note.relative_path, note.modified];
+
+        if let Some(score) = scores.and_then(|scores| scores.get(i)) {
+            row.push(format!("{score:.2}"));
+        }
+
+        table.add_row(row);
     }
 
     table.to_string()
 }
 
+/// Prints notes found by a search.
+///
+/// * `hits` - the notes to print, in order
+/// * `output` - print a table (see [build_table]), a JSON array of the
+///   note records, or one relative path per line for piping into other
+///   commands. Scores are only shown in the table.
+pub fn print_notes(hits: Vec<Hit>, output: &OutputArgs) -> anyhow::Result<()> {
+    let scores = output
+        .score
+        .then(|| {
+            hits.iter()
+                .map(|hit| hit.score)
+                .collect::<Option<Vec<f64>>>()
+        })
+        .flatten();
+    let notes = hits.into_iter().map(|hit| hit.note).collect::<Vec<Note>>();
+
+    match output.format {
+        OutputFormat::Table => println!("{}", build_table(notes, scores.as_deref())),
+        OutputFormat::Json => println!("{}", serde_json::to_string(&notes)?),
+        OutputFormat::Paths => {
+            for note in notes {
+                println!("{}", note.relative_path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Disambiguates user input into a single note, optionally creating a new note
 /// if no existing notes matched the input.
 ///
@@ -63,51 +131,407 @@ pub fn build_table(notes: Vec<Note>) -> String {
 /// If `create_if_empty` is false and no existing notes match the user input,
 /// we notify the user that no notes were found and exit gracefully.
 ///
+/// If prompting is disabled (see [prompt::is_interactive]), we never create
+/// a note. Instead we exit with [prompt::EXIT_NO_MATCHES] if no notes match,
+/// or list the candidates on stderr and exit with [prompt::EXIT_MULTIPLE_MATCHES]
+/// if several notes match.
+///
+/// If nothing matches exactly, the user chooses from similar notes instead
+/// (see [rank_similar]), unless `--exact` was given. A similar note is never
+/// used without asking: if prompting is disabled, the similar notes are listed
+/// on stderr and we exit with [prompt::EXIT_NO_MATCHES].
+///
 /// Some actions such as deleting a note don't make sense to prompt for creation.
 pub fn get_note(path: &str, create_if_empty: bool) -> anyhow::Result<Note> {
     let path = NotePath::parse(path)?;
-    let index = Index::open()?;
+    let mut matches = find_matches(&path)?;
 
-    let mut matches = if path.has_parent() {
-        index.find_by_path(&path)?
-    } else {
-        index.find_by_title(&path.title)?
-    };
+    if matches.is_empty() && !EXACT.load(Ordering::Relaxed) {
+        let index = Index::open()?;
+        let similar = rank_similar(index.get_all()?, &path);
+        drop(index);
 
-    // HOTFIX: #3
-    drop(index);
+        if !similar.is_empty() {
+            if prompt::is_interactive() {
+                return choose_similar(similar, &path, create_if_empty);
+            }
+
+            eprintln!("{}", "No exact match found. Similar notes:".bright_red());
+
+            for note in &similar {
+                eprintln!("{}", note.relative_path);
+            }
+        }
+    }
 
     let note = match matches.len() {
         0 => {
-            if create_if_empty && prompt::no_matches()? {
+            if create_if_empty && prompt::is_interactive() && prompt::no_matches()? {
                 let tags = Vec::new();
                 create_note(&path, &tags)?
             } else {
-                println!("{}", "Found 0 matching notes".bright_red());
-                std::process::exit(0);
+                exit_no_matches();
             }
         }
         1 => matches.pop().unwrap(),
-        _ => {
-            let options = matches
+        _ => choose_match(matches)?,
+    };
+
+    Ok(note)
+}
+
+/// Disambiguates user input into a single note, or lets the user choose one
+/// with fuzzy finding if no input was given.
+///
+/// * `path` - optional raw user input such as `foo/bar`
+/// * `create_if_empty` - see [get_note]
+pub fn get_or_select_note(path: Option<&str>, create_if_empty: bool) -> anyhow::Result<Note> {
+    match path {
+        Some(path) => get_note(path, create_if_empty),
+        None => select_note(Index::open()?.get_all()?),
+    }
+}
+
+/// Like [get_or_select_note], but lets the user choose several notes
+/// if no input was given.
+pub fn get_or_select_notes(path: Option<&str>, create_if_empty: bool) -> anyhow::Result<Vec<Note>> {
+    match path {
+        Some(path) => Ok(vec![get_note(path, create_if_empty)?]),
+        None => select_notes(Index::open()?.get_all()?),
+    }
+}
+
+/// Reports that no notes matched and exits with [prompt::EXIT_NO_MATCHES].
+pub fn exit_no_matches() -> ! {
+    eprintln!("{}", "Found 0 matching notes".bright_red());
+    std::process::exit(prompt::EXIT_NO_MATCHES);
+}
+
+/// Lists notes that could have been meant on stderr and exits with
+/// [prompt::EXIT_MULTIPLE_MATCHES].
+fn exit_multiple_matches(message: &str, notes: &[Note]) -> ! {
+    eprintln!("{}", message.bright_red());
+
+    for note in notes {
+        eprintln!("{}", note.relative_path);
+    }
+
+    std::process::exit(prompt::EXIT_MULTIPLE_MATCHES);
+}
+
+/// Prompts the user to choose a single note with fuzzy finding.
+///
+/// Each option shows the relative path of the note along with its
+/// modified time and tags. If fzf is used (see [prompt::use_fzf]),
+/// the content of the highlighted note is previewed alongside.
+pub fn select_note(mut notes: Vec<Note>) -> anyhow::Result<Note> {
+    if notes.is_empty() {
+        exit_no_matches();
+    }
+
+    notes.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    let selection = if prompt::use_fzf() {
+        select_one_fzf(&notes, None, None)?
+    } else {
+        let options = format_options(&notes);
+        let options = options.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        prompt::select_fuzzy(&options)?
+    };
+
+    Ok(notes.swap_remove(selection))
+}
+
+/// Prompts the user to choose any number of notes.
+///
+/// See [select_note] for how the options are displayed.
+pub fn select_notes(mut notes: Vec<Note>) -> anyhow::Result<Vec<Note>> {
+    if notes.is_empty() {
+        exit_no_matches();
+    }
+
+    notes.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    let selection = if prompt::use_fzf() {
+        select_fzf(&notes, true, None, None)?
+    } else {
+        let options = format_options(&notes);
+        let options = options.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        prompt::select_multiple(&options)?
+    };
+
+    Ok(notes
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| selection.contains(i))
+        .map(|(_, note)| note)
+        .collect())
+}
+
+/// Prompts the user to choose notes with fzf (see [prompt::select_fzf]),
+/// using [format_options] for the options and previewing each note.
+///
+/// * `header` - optional text displayed above the options
+/// * `extra` - an option listed after the notes, such as creating a new
+///   note, whose index is `notes.len()`
+fn select_fzf(
+    notes: &[Note],
+    multi: bool,
+    header: Option<&str>,
+    extra: Option<&str>,
+) -> anyhow::Result<Vec<usize>> {
+    let mut options = format_options(notes);
+    let mut files = notes
+        .iter()
+        .map(|n| n.absolute_path.as_str())
+        .collect::<Vec<&str>>();
+
+    if let Some(extra) = extra {
+        options.push(extra.to_string());
+        // nothing to preview
+        files.push("/dev/null");
+    }
+
+    let options = options.iter().map(String::as_str).collect::<Vec<&str>>();
+
+    prompt::select_fzf(&options, &files, multi, header)
+}
+
+/// Like [select_fzf], but for a single note. Exits if nothing was chosen.
+fn select_one_fzf(
+    notes: &[Note],
+    header: Option<&str>,
+    extra: Option<&str>,
+) -> anyhow::Result<usize> {
+    match select_fzf(notes, false, header, extra)?.first() {
+        Some(&selection) => Ok(selection),
+        None => std::process::exit(0),
+    }
+}
+
+/// Formats notes as aligned rows of relative path, modified time and tags
+/// for use as prompt options.
+pub fn format_options(notes: &[Note]) -> Vec<String> {
+    let width = notes
+        .iter()
+        .map(|n| n.relative_path.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    notes
+        .iter()
+        .map(|note| {
+            let mut tags = note.tags.iter().map(String::as_str).collect::<Vec<&str>>();
+            tags.sort();
+
+            let row = format!("{:width$}  {}", note.relative_path, note.modified);
+
+            if tags.is_empty() {
+                row
+            } else {
+                format!("{row}  [{}]", tags.join(", "))
+            }
+        })
+        .collect()
+}
+
+/// Disambiguates user input into a single note, creating a new note without
+/// prompting if no existing notes matched the input.
+///
+/// * `path` - raw user input such as `foo/bar`
+///
+/// This is intended for non-interactive actions such as writing piped input
+/// into a note. See [get_note] for the interactive equivalent.
+pub fn get_or_create_note(path: &str) -> anyhow::Result<Note> {
+    let path = NotePath::parse(path)?;
+    let mut matches = find_matches(&path)?;
+
+    match matches.len() {
+        0 => create_note(&path, &[]),
+        1 => Ok(matches.pop().unwrap()),
+        _ => choose_match(matches),
+    }
+}
+
+/// Finds the notes matching a path, or a title if the path has no parent.
+fn find_matches(path: &NotePath) -> anyhow::Result<Vec<Note>> {
+    let index = Index::open()?;
+
+    let matches = index.find(path)?;
+
+    // HOTFIX: #3
+    drop(index);
+
+    Ok(matches)
+}
+
+/// Ranks notes by how closely they match user input that matched nothing
+/// exactly, returning the most similar ones first.
+///
+/// Titles and aliases are compared to the input, or relative paths if the
+/// input has a parent folder. Notes starting with the input come first,
+/// then notes containing it, then notes that fuzzy match it.
+pub fn rank_similar(notes: Vec<Note>, path: &NotePath) -> Vec<Note> {
+    let matcher = SkimMatcherV2::default();
+    let input = path.relative_path().to_lowercase();
+
+    let mut ranked = notes
+        .into_iter()
+        .filter_map(|note| {
+            // lower is better: (tier, negated fuzzy score)
+            let rank = similar_names(&note, path)
                 .iter()
-                .map(|n| n.relative_path.as_str())
-                .collect::<Vec<&str>>();
+                .filter_map(|name| {
+                    let score = matcher.fuzzy_match(name, &input)?;
+
+                    let tier = if name.starts_with(&input) {
+                        0
+                    } else if name.contains(&input) {
+                        1
+                    } else {
+                        2
+                    };
+
+                    Some((tier, -score))
+                })
+                .min()?;
+
+            Some((rank, note))
+        })
+        .collect::<Vec<_>>();
+
+    ranked.sort_by(|(a, x), (b, y)| {
+        a.cmp(b)
+            .then(x.relative_path.len().cmp(&y.relative_path.len()))
+            .then(x.relative_path.cmp(&y.relative_path))
+    });
+
+    ranked
+        .into_iter()
+        .take(MAX_SIMILAR)
+        .map(|(_, note)| note)
+        .collect()
+}
+
+/// The lowercase names of a note that [rank_similar] compares to the input.
+fn similar_names(note: &Note, path: &NotePath) -> Vec<String> {
+    let names = std::iter::once(&note.title).chain(&note.aliases);
 
-            let selection = prompt::multiple_matches(&options)?;
-            matches.swap_remove(selection)
+    // compare paths, with aliases standing in for the note's title
+    let folder = match &path.parent {
+        Some(_) => note.relative_path.rsplit_once('/').map(|(f, _)| f),
+        None => None,
+    };
+
+    names
+        .map(|name| match folder {
+            Some(folder) => format!("{folder}/{name}").to_lowercase(),
+            None => name.to_lowercase(),
+        })
+        .collect()
+}
+
+/// Prompts the user to choose one of the notes similar to their input,
+/// or to create a new note at the input path if `create` is true.
+///
+/// This asks even if there is a single similar note, since it may not be
+/// the note the user meant.
+fn choose_similar(mut similar: Vec<Note>, path: &NotePath, create: bool) -> anyhow::Result<Note> {
+    let create_option = format!("Create new note: {}", path.relative_path());
+    let header = "No exact match found. Did you mean";
+
+    let selection = if prompt::use_fzf() {
+        let extra = create.then(|| create_option.bright_green().to_string());
+
+        select_one_fzf(&similar, Some(header), extra.as_deref())?
+    } else {
+        let mut options = similar
+            .iter()
+            .map(|n| n.relative_path.as_str())
+            .collect::<Vec<&str>>();
+
+        if create {
+            options.push(&create_option);
         }
+
+        prompt::similar_matches(&options)?
     };
 
-    Ok(note)
+    if selection == similar.len() {
+        create_note(path, &[])
+    } else {
+        Ok(similar.swap_remove(selection))
+    }
+}
+
+/// Prompts the user to choose one of several matching notes.
+///
+/// If fzf is used (see [prompt::use_fzf]), the content of the highlighted
+/// note is previewed alongside, which helps with similarly named notes.
+fn choose_match(mut matches: Vec<Note>) -> anyhow::Result<Note> {
+    if !prompt::is_interactive() {
+        let message = format!("Found {} matching notes:", matches.len());
+        exit_multiple_matches(&message, &matches);
+    }
+
+    let selection = if prompt::use_fzf() {
+        let header = Some("Multiple notes found. Please choose one");
+
+        select_one_fzf(&matches, header, None)?
+    } else {
+        let options = matches
+            .iter()
+            .map(|n| n.relative_path.as_str())
+            .collect::<Vec<&str>>();
+
+        prompt::multiple_matches(&options)?
+    };
+
+    Ok(matches.swap_remove(selection))
+}
+
+/// Reads everything piped into stdin.
+///
+/// Returns an error if stdin is a terminal, since there is nothing to read.
+pub fn read_stdin() -> anyhow::Result<String> {
+    let stdin = std::io::stdin();
+
+    if stdin.is_terminal() {
+        return Err(anyhow::anyhow!("Error: No input was piped to stdin"));
+    }
+
+    Ok(std::io::read_to_string(stdin)?)
+}
+
+/// Normalizes a folder path given by the user, such as `/foo/bar/`
+/// becoming `foo/bar`.
+///
+/// Returns an error if the input does not name a folder, or if it could
+/// point outside the root directory (see [NotePath::parse_contained]).
+pub fn parse_folder(input: &str) -> anyhow::Result<String> {
+    let folder = input.trim_matches('/');
+
+    if folder.is_empty() {
+        return Err(anyhow::anyhow!("Error: No folder provided"));
+    }
+
+    if !path::is_contained(folder) {
+        return Err(anyhow::anyhow!("Error: Invalid folder path: {folder}"));
+    }
+
+    Ok(folder.to_string())
 }
 
 /// Creates a new note both on disk and in the index.
 ///
 /// * `path` - raw input from the user such as `foo/bar`
-/// * `tags` - a slice of String representing tags given by the user
+/// * `tags` - a slice of String representing tags given by the user.
+///   Returns an error if any tag isn't valid. See [tags::normalize]
 pub fn create_note(path: &NotePath, tags: &[String]) -> anyhow::Result<Note> {
-    let note = Note::new(path, tags);
+    let tags = tags::normalize_all(tags)?;
+    let note = Note::new(path, &tags);
 
     file::create_file(path)?;
 
@@ -117,6 +541,17 @@ pub fn create_note(path: &NotePath, tags: &[String]) -> anyhow::Result<Note> {
     Ok(note)
 }
 
+/// Updates a note's inline tags and aliases from the current content of
+/// its file. See [Note::sync_content]
+pub fn sync_note(note: &mut Note) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(&note.absolute_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read note file: {}: {e}", note.absolute_path))?;
+
+    note.sync_content(&content);
+
+    Ok(())
+}
+
 /// Opens a note in the user's editor per the `$EDITOR` variable
 ///
 /// * `path` - absolute disk path (with `.md` extension) to a note
@@ -149,3 +584,52 @@ fn hash_note(path: &str) -> anyhow::Result<u64> {
 
     Ok(hasher.finish())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rank(notes: &[&str], input: &str) -> Vec<String> {
+        let notes = notes
+            .iter()
+            .map(|n| Note::new(&NotePath::parse(n).unwrap(), &[]))
+            .collect();
+
+        rank_similar(notes, &NotePath::parse(input).unwrap())
+            .into_iter()
+            .map(|n| n.relative_path)
+            .collect()
+    }
+
+    #[test]
+    fn parse_folder_paths() {
+        assert_eq!(parse_folder("/foo/bar/").unwrap(), "foo/bar");
+        assert!(parse_folder("/").is_err());
+        assert!(parse_folder("../elsewhere").is_err());
+        assert!(parse_folder("foo/./bar").is_err());
+        assert!(parse_folder("foo//bar").is_err());
+    }
+
+    #[test]
+    fn rank_similar_notes() {
+        let notes = [
+            "team-meeting",
+            "work/meeting-notes",
+            "meet",
+            "mtg/make-every-test",
+            "unrelated",
+        ];
+
+        assert_eq!(
+            rank(&notes, "meet"),
+            vec![
+                "meet",
+                "work/meeting-notes",
+                "team-meeting",
+                "mtg/make-every-test"
+            ]
+        );
+        assert_eq!(rank(&notes, "work/meet"), vec!["work/meeting-notes"]);
+        assert!(rank(&notes, "xyz").is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant, SystemTime},
+};
+
+use colored::Colorize;
+use notify::{
+    event::{ModifyKind, RenameMode},
+    Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+
+use crate::{config, import, index::Index, path::NotePath};
+
+/// How long a removed note is kept in the index, in case it turns out to
+/// be renamed or rewritten (as some editors do when saving).
+const REMOVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Watches the root directory and keeps the index in sync with changes
+/// made by other tools, until interrupted.
+pub fn run() -> anyhow::Result<()> {
+    let root = Path::new(&config::get_root()).canonicalize()?;
+    let (tx, rx) = mpsc::channel();
+
+    let mut watcher = RecommendedWatcher::new(tx, Config::default())
+        .map_err(|e| anyhow::anyhow!("Failed to start watching files: {e}"))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| anyhow::anyhow!("Failed to watch {}: {e}", root.display()))?;
+
+    println!(
+        "{}",
+        format!("Watching {} for changes", root.display()).bright_green()
+    );
+
+    let mut sync = IndexSync::new(root);
+
+    loop {
+        match rx.recv_timeout(REMOVE_DELAY) {
+            Ok(Ok(event)) => {
+                if let Err(e) = sync.handle(event) {
+                    eprintln!("{}", e.to_string().bright_red());
+                }
+            }
+            Ok(Err(e)) => eprintln!("{}", format!("Failed to watch files: {e}").bright_red()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if let Err(e) = sync.flush(REMOVE_DELAY) {
+            eprintln!("{}", e.to_string().bright_red());
+        }
+    }
+
+    Ok(())
+}
+
+/// A file that disappeared, waiting to see if it comes back.
+#[derive(Debug)]
+struct Removal {
+    path: PathBuf,
+    /// Links the two halves of a rename (see [Event::tracker])
+    tracker: Option<usize>,
+    since: Instant,
+}
+
+/// Applies filesystem events to the index.
+///
+/// The index is only opened while handling an event, so other jottem
+/// commands can run while watching.
+#[derive(Debug)]
+pub struct IndexSync {
+    root: PathBuf,
+    removals: Vec<Removal>,
+}
+
+impl IndexSync {
+    /// * `root` - the canonical root directory the events come from
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            removals: Vec::new(),
+        }
+    }
+
+    /// Updates the index for a single event.
+    ///
+    /// Files that are created or changed are indexed or synced. Removed
+    /// files (or the old half of a rename) are only removed from the index
+    /// by [flush](Self::flush), so that a rename moves the record instead
+    /// of replacing it with a new one.
+    ///
+    /// Renames can only be followed when the platform reports both halves
+    /// with the same tracker. Otherwise (including [RenameMode::Any], and
+    /// files moved in from outside the root) the old note is removed and
+    /// the new file is imported, losing manual tags and the created time.
+    pub fn handle(&mut self, event: Event) -> anyhow::Result<()> {
+        let tracker = event.tracker();
+
+        match event.kind {
+            EventKind::Create(_) => self.update_all(&event.paths),
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                self.queue_removals(&event.paths, tracker);
+                Ok(())
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                let from = tracker.and_then(|tracker| {
+                    let i = self
+                        .removals
+                        .iter()
+                        .position(|r| r.tracker == Some(tracker))?;
+
+                    Some(self.removals.remove(i).path)
+                });
+
+                match (from, event.paths.first()) {
+                    (Some(from), Some(to)) => self.rename(&from, to),
+                    _ => self.update_all(&event.paths),
+                }
+            }
+            // already handled by the `From` and `To` halves
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => Ok(()),
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                let (exists, gone) = event
+                    .paths
+                    .iter()
+                    .cloned()
+                    .partition::<Vec<_>, _>(|p| p.exists());
+
+                self.queue_removals(&gone, None);
+                self.update_all(&exists)
+            }
+            EventKind::Modify(_) => self.update_all(&event.paths),
+            EventKind::Remove(_) => {
+                self.queue_removals(&event.paths, None);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes notes whose files have been gone for at least `delay`.
+    /// Files that came back in the meantime are synced instead.
+    pub fn flush(&mut self, delay: Duration) -> anyhow::Result<()> {
+        let (due, waiting) = std::mem::take(&mut self.removals)
+            .into_iter()
+            .partition::<Vec<_>, _>(|r| r.since.elapsed() >= delay);
+
+        self.removals = waiting;
+
+        for removal in due {
+            if removal.path.exists() {
+                self.update(&removal.path)?;
+            } else {
+                self.remove(&removal.path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn queue_removals(&mut self, paths: &[PathBuf], tracker: Option<usize>) {
+        for path in paths {
+            self.removals.push(Removal {
+                path: path.clone(),
+                tracker,
+                since: Instant::now(),
+            });
+        }
+    }
+
+    fn update_all(&mut self, paths: &[PathBuf]) -> anyhow::Result<()> {
+        for path in paths {
+            // the file is back, so whatever removed it was really a rewrite
+            self.removals.retain(|r| &r.path != path);
+            self.update(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Indexes a new note file, or syncs the content and modified time of
+    /// an existing one. Folders are updated recursively.
+    fn update(&self, path: &Path) -> anyhow::Result<()> {
+        if path.is_dir() {
+            let mut files = Vec::new();
+            import::collect_files(path, &mut files)?;
+
+            return files.iter().try_for_each(|file| self.update(file));
+        }
+
+        let Some(note_path) = self.note_path(path) else {
+            return Ok(());
+        };
+
+        if !path.is_file() {
+            return Ok(());
+        }
+
+        let index = Index::open()?;
+
+        match index.find_by_path(&note_path)?.pop() {
+            Some(mut note) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read file: {}: {e}", path.display()))?;
+                let modified = std::fs::metadata(path)?
+                    .modified()
+                    .unwrap_or_else(|_| SystemTime::now());
+
+                let before = (
+                    note.modified.clone(),
+                    note.tags.clone(),
+                    note.aliases.clone(),
+                );
+
+                note.sync_content(&content);
+                note.modified = import::format_time(modified);
+
+                if before
+                    != (
+                        note.modified.clone(),
+                        note.tags.clone(),
+                        note.aliases.clone(),
+                    )
+                {
+                    index.insert(&note)?;
+                    println!("Updated {}", note.relative_path);
+                }
+            }
+            None => {
+                let note = import::import_file(path, &note_path, true)?;
+                index.insert(&note)?;
+
+                println!("Added {}", note.relative_path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the note at `path`, or every note under it if it was a folder.
+    fn remove(&self, path: &Path) -> anyhow::Result<()> {
+        let Some(relative) = self.relative_path(path) else {
+            return Ok(());
+        };
+
+        let index = Index::open()?;
+        let notes = match self.note_path(path) {
+            Some(note_path) => index.find_by_path(&note_path)?,
+            None => index.find_by_folder(&relative)?,
+        };
+
+        for note in notes {
+            index.remove(note.id())?;
+            println!("Removed {}", note.relative_path);
+        }
+
+        Ok(())
+    }
+
+    /// Moves the records of a renamed note, or of every note in a renamed
+    /// folder, keeping their tags and created time.
+    ///
+    /// Notes that can't be moved (such as ones renamed to an invalid path)
+    /// are reported and removed, without stopping the others.
+    fn rename(&mut self, from: &Path, to: &Path) -> anyhow::Result<()> {
+        let (Some(old), Some(new)) = (self.relative_path(from), self.relative_path(to)) else {
+            // moved in or out of the root (or a hidden folder)
+            self.queue_removals(&[from.to_path_buf()], None);
+            return self.update(to);
+        };
+
+        let index = Index::open()?;
+        let mut moves = Vec::new();
+
+        if to.is_dir() {
+            for note in index.find_by_folder(&old)? {
+                let moved = format!("{new}{}", &note.relative_path[old.len()..]);
+                moves.push((note, moved));
+            }
+        } else if let (Some(from_path), Some(to_path)) = (self.note_path(from), self.note_path(to))
+        {
+            for note in index.find_by_path(&from_path)? {
+                moves.push((note, to_path.relative_path()));
+            }
+        } else {
+            // renamed to or from something that isn't a note, such as a backup file
+            drop(index);
+            self.queue_removals(&[from.to_path_buf()], None);
+            return self.update(to);
+        }
+
+        for (mut note, new_path) in moves {
+            let id = note.id();
+            let old_path = note.relative_path.clone();
+
+            // one bad file name shouldn't stop the rest of a folder from moving
+            let new_path = match NotePath::parse_contained(&new_path) {
+                Ok(new_path) => new_path,
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        format!("Failed to move {old_path} to {new_path}: {e}").bright_red()
+                    );
+                    index.remove(id)?;
+                    println!("Removed {old_path}");
+                    continue;
+                }
+            };
+
+            note.relative_path = new_path.relative_path();
+            note.absolute_path = new_path.absolute_path_with_ext();
+            note.title = new_path.title.clone();
+
+            index.insert(&note)?;
+            index.remove(id)?;
+
+            println!("Moved {old_path} to {}", note.relative_path);
+        }
+
+        drop(index);
+
+        // pick up content changes and any files that weren't indexed yet
+        self.update(to)
+    }
+
+    /// The path of a file or folder relative to the root, without a `.md`
+    /// extension, or `None` if it's outside the root or hidden.
+    fn relative_path(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+
+        if relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+        {
+            return None;
+        }
+
+        let relative = relative
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        let relative = relative.strip_suffix(".md").unwrap_or(&relative);
+
+        (!relative.is_empty()).then(|| relative.to_string())
+    }
+
+    /// The note a path refers to, if it's a markdown file inside the root.
+    fn note_path(&self, path: &Path) -> Option<NotePath> {
+        if path.extension().is_none_or(|ext| ext != "md") {
+            return None;
+        }
+
+        NotePath::parse_contained(&self.relative_path(path)?).ok()
+    }
+}
@@ -1,4 +1,7 @@
-use jottem::{cli::SearchArgs, path::NotePath};
+use jottem::{
+    cli::{Conflict, OutputArgs, OutputFormat, SearchArgs, SearchCommand},
+    path::NotePath,
+};
 use serial_test::serial;
 use tempfile::{tempdir, TempDir};
 
@@ -41,11 +44,503 @@ fn test_find_note_by_title() {
 
     let search = SearchArgs {
         path: Some("test_note".into()),
-        tags: Vec::new(),
-        all: false,
+        ..SearchArgs::default()
     };
 
-    let result = jottem::find_notes(&search);
+    let output = OutputArgs {
+        format: OutputFormat::Table,
+        score: true,
+    };
+    let result = jottem::find_notes(&search, &output);
 
     assert!(result.is_ok());
 }
+
+#[test]
+#[serial]
+fn test_find_notes_by_path_pattern() {
+    let _tmp = setup();
+
+    for path in [
+        "projects/alpha/todo",
+        "projects/beta/todo",
+        "projects/notes",
+        "todo",
+    ] {
+        let path = NotePath::parse(path).unwrap();
+        jottem::utils::create_note(&path, &Vec::new()).unwrap();
+    }
+
+    let index = jottem::index::Index::open().unwrap();
+    let paths = |notes: Vec<jottem::note::Note>| {
+        let mut paths = notes
+            .into_iter()
+            .map(|n| n.relative_path)
+            .collect::<Vec<String>>();
+        paths.sort();
+        paths
+    };
+
+    assert_eq!(
+        paths(index.find_by_glob("projects/*/todo").unwrap()),
+        vec!["projects/alpha/todo", "projects/beta/todo"]
+    );
+    assert_eq!(paths(index.find_by_glob("**/todo").unwrap()).len(), 3);
+    assert_eq!(paths(index.find_by_folder("projects/").unwrap()).len(), 3);
+
+    let regex = |pattern: &str| SearchArgs {
+        regex: Some(pattern.into()),
+        ..SearchArgs::default()
+    };
+
+    let hits = jottem::search::Query::parse(&regex("^projects/[^/]+$"))
+        .unwrap()
+        .run(&index)
+        .unwrap();
+    assert_eq!(
+        paths(hits.into_iter().map(|hit| hit.note).collect()),
+        vec!["projects/notes"]
+    );
+    assert!(jottem::search::Query::parse(&regex("(")).is_err());
+}
+
+#[test]
+#[serial]
+fn test_ranked_text_search() {
+    let _tmp = setup();
+
+    let notes = [
+        (
+            "archive/meeting",
+            "We went over the budget among many other things.",
+        ),
+        (
+            "finance/budget",
+            "Budget for the year, with the budget per month.",
+        ),
+        ("misc/groceries", "Nothing about money here."),
+    ];
+
+    for (path, content) in notes {
+        let path = NotePath::parse(path).unwrap();
+        let note = jottem::utils::create_note(&path, &Vec::new()).unwrap();
+        std::fs::write(&note.absolute_path, content).unwrap();
+    }
+
+    let search = |sort| SearchArgs {
+        text: Some("Budget".into()),
+        sort,
+        ..SearchArgs::default()
+    };
+
+    let index = jottem::index::Index::open().unwrap();
+    let hits = jottem::search::Query::parse(&search(None))
+        .unwrap()
+        .run(&index)
+        .unwrap();
+
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].note.relative_path, "finance/budget");
+    assert!(hits[0].score > hits[1].score);
+
+    let hits = jottem::search::Query::parse(&search(Some(jottem::cli::SortOrder::Path)))
+        .unwrap()
+        .run(&index)
+        .unwrap();
+
+    assert_eq!(hits[0].note.relative_path, "archive/meeting");
+    assert_eq!(hits[1].note.relative_path, "finance/budget");
+}
+
+#[test]
+#[serial]
+fn test_saved_searches() {
+    let _tmp = setup();
+
+    let path = NotePath::parse("daily/standup").unwrap();
+    jottem::utils::create_note(&path, &["standup".into()]).unwrap();
+
+    let args = SearchArgs {
+        tags: vec!["standup".into()],
+        modified_after: Some("7d".into()),
+        ..SearchArgs::default()
+    };
+
+    jottem::manage_searches(SearchCommand::Save {
+        name: " standup ".into(),
+        args: Box::new(args),
+    })
+    .unwrap();
+
+    let index = jottem::index::Index::open().unwrap();
+    let searches = index.get_searches().unwrap();
+    assert_eq!(searches.len(), 1);
+    assert_eq!(searches[0].0, "standup");
+
+    let notes = jottem::search::Query::parse(&searches[0].1)
+        .unwrap()
+        .run(&index)
+        .unwrap();
+    assert_eq!(notes.len(), 1);
+    drop(index);
+
+    let run = |name: &str| SearchCommand::Run {
+        name: name.into(),
+        output: OutputArgs {
+            format: OutputFormat::Paths,
+            score: false,
+        },
+    };
+    assert!(jottem::manage_searches(run("standup")).is_ok());
+    assert!(jottem::manage_searches(run("missing")).is_err());
+
+    jottem::manage_searches(SearchCommand::Delete {
+        name: "standup".into(),
+    })
+    .unwrap();
+    assert!(jottem::manage_searches(run("standup")).is_err());
+}
+
+#[test]
+#[serial]
+fn test_watch_sync() {
+    use notify::{
+        event::{CreateKind, ModifyKind, RemoveKind, RenameMode},
+        Event, EventKind,
+    };
+    use std::time::Duration;
+
+    let tmp = setup();
+    let root = tmp.path().canonicalize().unwrap();
+    let mut sync = jottem::watch::IndexSync::new(root.clone());
+
+    let find = |path: &str| {
+        let index = jottem::index::Index::open().unwrap();
+        index
+            .find_by_path(&NotePath::parse(path).unwrap())
+            .unwrap()
+            .pop()
+    };
+
+    let path = NotePath::parse("a/note").unwrap();
+    let note = jottem::utils::create_note(&path, &["keep".into()]).unwrap();
+
+    // a rename moves the record instead of replacing it
+    std::fs::create_dir(root.join("b")).unwrap();
+    std::fs::rename(root.join("a/note.md"), root.join("b/moved.md")).unwrap();
+
+    let rename = |mode, path: &str| {
+        Event::new(EventKind::Modify(ModifyKind::Name(mode)))
+            .add_path(root.join(path))
+            .set_tracker(7)
+    };
+    sync.handle(rename(RenameMode::From, "a/note.md")).unwrap();
+    sync.handle(rename(RenameMode::To, "b/moved.md")).unwrap();
+    sync.flush(Duration::ZERO).unwrap();
+
+    let moved = find("b/moved").unwrap();
+    assert!(find("a/note").is_none());
+    assert!(moved.tags.contains("keep"));
+    assert_eq!(moved.created, note.created);
+
+    // new files are indexed with their inline tags
+    std::fs::write(root.join("c.md"), "an #idea").unwrap();
+    let create = Event::new(EventKind::Create(CreateKind::File)).add_path(root.join("c.md"));
+    sync.handle(create.clone()).unwrap();
+    assert!(find("c").unwrap().tags.contains("idea"));
+
+    // a file that is removed and written again keeps its record
+    let remove =
+        |path: &str| Event::new(EventKind::Remove(RemoveKind::File)).add_path(root.join(path));
+    sync.handle(remove("c.md")).unwrap();
+    sync.handle(create).unwrap();
+    sync.flush(Duration::ZERO).unwrap();
+    assert!(find("c").is_some());
+
+    // renaming a folder moves every note in it
+    std::fs::rename(root.join("b"), root.join("d")).unwrap();
+    sync.handle(rename(RenameMode::From, "b")).unwrap();
+    sync.handle(rename(RenameMode::To, "d")).unwrap();
+    sync.flush(Duration::ZERO).unwrap();
+    assert!(find("b/moved").is_none());
+    assert!(find("d/moved").unwrap().tags.contains("keep"));
+
+    std::fs::remove_file(root.join("d/moved.md")).unwrap();
+    sync.handle(remove("d/moved.md")).unwrap();
+    sync.flush(Duration::ZERO).unwrap();
+    assert!(find("d/moved").is_none());
+}
+
+#[test]
+#[serial]
+fn test_move_folder() {
+    let tmp = setup();
+
+    let path = NotePath::parse("old/nested/test_note").unwrap();
+    jottem::utils::create_note(&path, &Vec::new()).unwrap();
+
+    jottem::move_folder("old", "new", false).unwrap();
+
+    assert!(tmp.path().join("new/nested/test_note.md").is_file());
+    assert!(!tmp.path().join("old").exists());
+
+    let index = jottem::index::Index::open().unwrap();
+    assert!(index.find_by_folder("old").unwrap().is_empty());
+    assert_eq!(index.find_by_folder("new").unwrap().len(), 1);
+}
+
+#[test]
+#[serial]
+fn test_delete_folder() {
+    let tmp = setup();
+
+    let path = NotePath::parse("old/nested/test_note").unwrap();
+    jottem::utils::create_note(&path, &Vec::new()).unwrap();
+
+    jottem::delete_folder("old/").unwrap();
+
+    assert!(!tmp.path().join("old").exists());
+
+    let index = jottem::index::Index::open().unwrap();
+    assert!(index.get_all().unwrap().is_empty());
+}
+
+#[test]
+#[serial]
+fn test_import_notes() {
+    let tmp = setup();
+    let vault = tempdir().unwrap();
+
+    std::fs::create_dir_all(vault.path().join("projects/.hidden")).unwrap();
+    std::fs::create_dir_all(vault.path().join(".obsidian")).unwrap();
+    std::fs::write(
+        vault.path().join("projects/todo.md"),
+        "---\ntags: [work]\n---\n# Todo\n\nShip it #urgent\n",
+    )
+    .unwrap();
+    std::fs::write(vault.path().join("projects/.hidden/secret.md"), "").unwrap();
+    std::fs::write(vault.path().join(".obsidian/app.md"), "").unwrap();
+    std::fs::write(vault.path().join("image.png"), "").unwrap();
+    // not valid UTF-8, so it is skipped without stopping the import
+    std::fs::write(vault.path().join("projects/binary.md"), [0xff, 0xfe]).unwrap();
+
+    assert!(jottem::import::import(vault.path(), Some("../outside"), false).is_err());
+
+    let summary = jottem::import::import(vault.path(), Some("vault"), false).unwrap();
+
+    assert_eq!((summary.imported, summary.skipped), (1, 1));
+    assert!(tmp.path().join("vault/projects/todo.md").exists());
+
+    let index = jottem::index::Index::open().unwrap();
+    let notes = index.get_all().unwrap();
+
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].relative_path, "vault/projects/todo");
+    assert!(notes[0].tags.contains("work") && notes[0].tags.contains("urgent"));
+    drop(index);
+
+    let summary = jottem::import::import(vault.path(), Some("vault"), false).unwrap();
+
+    assert_eq!((summary.imported, summary.skipped), (0, 2));
+}
+
+#[test]
+#[serial]
+fn test_backup_and_restore() {
+    let tmp = setup();
+    let archive = tmp.path().join("backup.tar.gz");
+
+    let path = NotePath::parse("nested/test_note").unwrap();
+    let note = jottem::utils::create_note(&path, &["test_tag".into()]).unwrap();
+    std::fs::write(&note.absolute_path, "hello").unwrap();
+
+    let stale = NotePath::parse("stale_note").unwrap();
+    let stale = jottem::utils::create_note(&stale, &Vec::new()).unwrap();
+    std::fs::remove_file(&stale.absolute_path).unwrap();
+
+    let summary = jottem::backup::backup(&archive).unwrap();
+    assert_eq!((summary.backed_up, summary.skipped), (1, 1));
+
+    let summary = jottem::backup::restore(&archive, Conflict::Skip).unwrap();
+    assert_eq!((summary.restored, summary.skipped), (0, 1));
+
+    let summary = jottem::backup::restore(&archive, Conflict::Rename).unwrap();
+    assert_eq!(summary.restored, 1);
+
+    let restored = tmp.path().join("nested/test_note-1.md");
+    assert_eq!(std::fs::read_to_string(restored).unwrap(), "hello");
+
+    let index = jottem::index::Index::open().unwrap();
+    let notes = index
+        .find_by_path(&NotePath::parse("nested/test_note-1").unwrap())
+        .unwrap();
+
+    assert_eq!(notes.len(), 1);
+    assert!(notes[0].tags.contains("test_tag"));
+    assert_eq!(notes[0].created, note.created);
+
+    // records of notes that aren't in the backup are left alone
+    let stale = NotePath::parse("stale_note").unwrap();
+    assert_eq!(index.find_by_path(&stale).unwrap().len(), 1);
+}
+
+#[test]
+#[serial]
+fn test_note_aliases() {
+    let tmp = setup();
+
+    let path = NotePath::parse("daily/2024-01-15").unwrap();
+    jottem::utils::create_note(&path, &Vec::new()).unwrap();
+
+    jottem::aliases::add_alias("daily/2024-01-15", "standup").unwrap();
+
+    let content = std::fs::read_to_string(tmp.path().join("daily/2024-01-15.md")).unwrap();
+    assert!(content.starts_with("---\naliases: [\"standup\"]\n---\n"));
+
+    let note = jottem::utils::get_note("standup", false).unwrap();
+    assert_eq!(note.relative_path, "daily/2024-01-15");
+
+    let note = jottem::utils::get_note("daily/standup", false).unwrap();
+    assert_eq!(note.relative_path, "daily/2024-01-15");
+
+    jottem::aliases::remove_alias("2024-01-15", "standup").unwrap();
+
+    let index = jottem::index::Index::open().unwrap();
+    assert!(index.find_by_title("standup").unwrap().is_empty());
+}
+
+#[test]
+#[serial]
+fn test_normalize_inline_tags() {
+    let _tmp = setup();
+
+    let path = NotePath::parse("test_note").unwrap();
+    let mut note = jottem::utils::create_note(&path, &Vec::new()).unwrap();
+    note.tags.insert("Manual".into());
+    note.tags.insert("Inline".into());
+    note.inline_tags.insert("Inline".into());
+
+    let index = jottem::index::Index::open().unwrap();
+    index.insert(&note).unwrap();
+    drop(index);
+
+    jottem::tags::normalize_index(false).unwrap();
+
+    let index = jottem::index::Index::open().unwrap();
+    let note = index.find_by_path(&path).unwrap().pop().unwrap();
+
+    assert!(note.tags.contains("manual") && note.tags.contains("inline"));
+    assert_eq!(note.inline_tags, ["inline".to_string()].into());
+}
+
+#[test]
+#[serial]
+fn test_rename_tag() {
+    let tmp = setup();
+
+    let manual = NotePath::parse("manual").unwrap();
+    jottem::utils::create_note(&manual, &["work".into()]).unwrap();
+    let modified = std::fs::metadata(tmp.path().join("manual.md"))
+        .unwrap()
+        .modified()
+        .unwrap();
+
+    let inline = NotePath::parse("inline").unwrap();
+    let mut note = jottem::utils::create_note(&inline, &Vec::new()).unwrap();
+    std::fs::write(&note.absolute_path, "a #work/alpha #Work note").unwrap();
+    note.sync_content("a #work/alpha #Work note");
+    jottem::index::Index::open().unwrap().insert(&note).unwrap();
+
+    // a missing file stops the rename before anything is changed
+    let missing = NotePath::parse("missing").unwrap();
+    jottem::utils::create_note(&missing, &["work".into()]).unwrap();
+    std::fs::remove_file(tmp.path().join("missing.md")).unwrap();
+
+    assert!(jottem::tags::rename_tag("Work", "office").is_err());
+    let content = std::fs::read_to_string(tmp.path().join("inline.md")).unwrap();
+    assert_eq!(content, "a #work/alpha #Work note");
+
+    std::fs::write(tmp.path().join("missing.md"), "").unwrap();
+    jottem::tags::rename_tag("Work", "office").unwrap();
+
+    // mixed-case hashtags are renamed too
+    let content = std::fs::read_to_string(tmp.path().join("inline.md")).unwrap();
+    assert_eq!(content, "a #office/alpha #office note");
+
+    let index = jottem::index::Index::open().unwrap();
+    let note = index.find_by_path(&inline).unwrap().pop().unwrap();
+    assert!(!note.tags.iter().any(|t| t.starts_with("work")));
+    drop(index);
+
+    let index = jottem::index::Index::open().unwrap();
+    let note = index.find_by_path(&manual).unwrap().pop().unwrap();
+    assert!(note.tags.contains("office") && !note.tags.contains("work"));
+
+    // files without hashtags to rename are left alone
+    let after = std::fs::metadata(tmp.path().join("manual.md"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(after, modified);
+}
+
+#[test]
+#[serial]
+fn test_similar_note_without_prompts() {
+    let tmp = setup();
+
+    for path in ["team-meeting", "work/meeting-notes"] {
+        let path = NotePath::parse(path).unwrap();
+        jottem::utils::create_note(&path, &Vec::new()).unwrap();
+    }
+
+    // a similar note is never deleted in place of the missing one
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_jottem"))
+        .args(["--no-input", "delete", "meet"])
+        .status()
+        .unwrap();
+
+    assert_eq!(status.code(), Some(jottem::prompt::EXIT_NO_MATCHES));
+    assert!(tmp.path().join("work/meeting-notes.md").exists());
+}
+
+#[test]
+#[serial]
+fn test_create_note_from_stdin() {
+    use std::io::Write;
+
+    let tmp = setup();
+
+    let nested = NotePath::parse("work/foo").unwrap();
+    jottem::utils::create_note(&nested, &Vec::new()).unwrap();
+
+    let write = |text: &str, tag: &str| {
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_jottem"))
+            .args(["create", "foo", "--stdin", "--tags", tag])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(text.as_bytes()).unwrap();
+        drop(stdin);
+
+        assert!(child.wait().unwrap().success());
+    };
+
+    // foo is created at the root even though work/foo has the same title,
+    // and the tags are added once it exists
+    write("first", "new");
+    write("second", "existing");
+
+    let content = std::fs::read_to_string(tmp.path().join("foo.md")).unwrap();
+    assert!(content.contains("first") && content.contains("second"));
+    assert!(std::fs::read_to_string(tmp.path().join("work/foo.md"))
+        .unwrap()
+        .is_empty());
+
+    let index = jottem::index::Index::open().unwrap();
+    let root = NotePath::parse("foo").unwrap();
+    let note = index.find_by_path(&root).unwrap().pop().unwrap();
+    assert!(note.tags.contains("new") && note.tags.contains("existing"));
+}